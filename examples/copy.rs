@@ -33,8 +33,6 @@ fn main() -> Result<()> {
     let cli = Cli::parse();
     eprintln!("reading from {:?}", cli.io.input);
     eprintln!("writing to {:?}", cli.io.output);
-    let mut input = cli.io.input.open()?;
-    let mut output = cli.io.output.open()?;
-    std::io::copy(&mut input, &mut output)?;
+    cli.io.copy()?;
     Ok(())
 }
@@ -19,16 +19,16 @@
 
 use anyhow::Result;
 
-use clap::Parser;
+use clap::{Parser, ValueHint};
 use clap_io::{Input, Output};
 
 /// Copy --in to --out
 #[derive(Debug, Parser)]
 struct Cli {
-    #[arg(long = "in")]
+    #[arg(long = "in", value_hint = ValueHint::FilePath)]
     input: Input,
 
-    #[arg(long = "out")]
+    #[arg(long = "out", value_hint = ValueHint::FilePath)]
     output: Output,
 }
 
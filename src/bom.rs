@@ -0,0 +1,105 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, Read};
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// A reader that skips a leading UTF-8 byte order mark.
+pub(crate) struct StripBom<R> {
+    inner: R,
+    prefix: [u8; 3],
+    start: usize,
+    end: usize,
+    checked: bool,
+}
+
+impl<R> StripBom<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            prefix: [0; 3],
+            start: 0,
+            end: 0,
+            checked: false,
+        }
+    }
+}
+
+impl<R: Read> StripBom<R> {
+    /// Read up to three bytes, since a short read may split the BOM.
+    fn check(&mut self) -> io::Result<()> {
+        while self.end < BOM.len() && self.prefix[..self.end] == BOM[..self.end] {
+            match self.inner.read(&mut self.prefix[self.end..]) {
+                Ok(0) => break,
+                Ok(n) => self.end += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        if self.prefix[..self.end] == *BOM {
+            self.start = self.end;
+        }
+        self.checked = true;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StripBom<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.checked {
+            self.check()?;
+        }
+        if self.start < self.end {
+            let n = buf.len().min(self.end - self.start);
+            buf[..n].copy_from_slice(&self.prefix[self.start..self.start + n]);
+            self.start += n;
+            return Ok(n);
+        }
+        self.inner.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read_all, OneByte};
+
+    const CASES: &[(&[u8], &[u8])] = &[
+        (b"\xEF\xBB\xBFabc", b"abc"),
+        (b"\xEF\xBB\xBF", b""),
+        (b"\xEF\xBBabc", b"\xEF\xBBabc"),
+        (b"\xEF\xBB", b"\xEF\xBB"),
+        (b"abc\xEF\xBB\xBF", b"abc\xEF\xBB\xBF"),
+        (b"", b""),
+    ];
+
+    #[test]
+    fn strip_one_byte_at_a_time() {
+        for &(input, expected) in CASES {
+            assert_eq!(
+                read_all(StripBom::new(OneByte(input))),
+                expected,
+                "{:?}",
+                input
+            );
+            assert_eq!(read_all(StripBom::new(input)), expected, "{:?}", input);
+        }
+    }
+}
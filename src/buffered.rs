@@ -0,0 +1,126 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, BufWriter, LineWriter, Write};
+
+use crate::OutputWriter;
+
+/// A buffered output stream, returned by [`Output::open_buffered`](crate::Output::open_buffered).
+///
+/// The buffer is flushed when the writer is dropped, but any error while flushing is ignored.
+/// Call [`BufferedOutput::finish`] to observe write errors.
+pub struct BufferedOutput {
+    inner: Buffer,
+}
+
+enum Buffer {
+    Block(BufWriter<Box<dyn Write + 'static>>),
+    Line(LineWriter<Box<dyn Write + 'static>>),
+}
+
+impl BufferedOutput {
+    pub(crate) fn new(inner: BufWriter<Box<dyn Write + 'static>>) -> Self {
+        Self {
+            inner: Buffer::Block(inner),
+        }
+    }
+
+    pub(crate) fn line(inner: LineWriter<Box<dyn Write + 'static>>) -> Self {
+        Self {
+            inner: Buffer::Line(inner),
+        }
+    }
+
+    /// Flush the buffer and the underlying stream, returning any error.
+    pub fn finish(mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+impl Write for BufferedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match &mut self.inner {
+            Buffer::Block(inner) => inner.write(buf),
+            Buffer::Line(inner) => inner.write(buf),
+        }
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        match &mut self.inner {
+            Buffer::Block(inner) => inner.write_all(buf),
+            Buffer::Line(inner) => inner.write_all(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.inner {
+            Buffer::Block(inner) => inner.flush(),
+            Buffer::Line(inner) => inner.flush(),
+        }
+    }
+}
+
+/// An output stream that syncs a file to disk, returned by
+/// [`Output::open_synced`](crate::Output::open_synced).
+///
+/// The file is synced when the writer is dropped, but any error is ignored. Call
+/// [`SyncedOutput::finish`] to observe it.
+pub struct SyncedOutput {
+    inner: Option<OutputWriter>,
+}
+
+impl SyncedOutput {
+    pub(crate) fn new(inner: OutputWriter) -> Self {
+        Self { inner: Some(inner) }
+    }
+
+    /// Flush the stream and sync a file's data and metadata to disk, returning any error.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.inner.take() {
+            Some(inner) => sync(inner),
+            None => Ok(()),
+        }
+    }
+}
+
+fn sync(mut inner: OutputWriter) -> io::Result<()> {
+    inner.flush()?;
+    match inner {
+        OutputWriter::File(file) => file.sync_all(),
+        _ => Ok(()),
+    }
+}
+
+impl Write for SyncedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.as_mut().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl Drop for SyncedOutput {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            let _ = sync(inner);
+        }
+    }
+}
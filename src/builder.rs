@@ -0,0 +1,262 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    ffi::OsString,
+    fs::OpenOptions,
+    io::{self, Read, Write},
+};
+
+use clap::{Arg, ArgMatches, Command, ValueHint};
+
+use crate::{Input, InputOutput, InputParser, Output, OutputParser};
+
+/// Configures the flags and open behavior of an [`InputOutput`].
+///
+/// The flags of a flattened [`InputOutput`] are fixed, so use this to share other names or
+/// behavior between many commands. Add the flags to a command with
+/// [`InputOutputBuilder::augment_args`], read them back with
+/// [`InputOutputBuilder::from_arg_matches`], and open the streams with
+/// [`ConfiguredInputOutput::open`] or [`ConfiguredInputOutput::copy`].
+///
+/// The flag names of a type used with `#[clap(flatten)]` are fixed at compile time, so this
+/// configures a [`Command`] at runtime instead.
+///
+/// ```rust,no_run
+/// use clap::Command;
+/// use clap_io::InputOutputBuilder;
+///
+/// let builder = InputOutputBuilder::new()
+///     .input_short('i')
+///     .output_long("out")
+///     .create_dirs(true);
+/// let matches = builder.augment_args(Command::new("tool")).get_matches();
+/// let io = builder.from_arg_matches(&matches);
+/// io.copy().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputOutputBuilder {
+    input_long: &'static str,
+    input_short: Option<char>,
+    output_long: &'static str,
+    output_short: Option<char>,
+    input_parser: InputParser,
+    output_parser: OutputParser,
+    append: bool,
+    create_dirs: bool,
+}
+
+impl InputOutputBuilder {
+    /// Create a builder with the same flags and behavior as [`InputOutput`].
+    pub fn new() -> Self {
+        Self {
+            input_long: "input",
+            input_short: None,
+            output_long: "output",
+            output_short: None,
+            input_parser: InputParser::new(),
+            output_parser: OutputParser::new(),
+            append: false,
+            create_dirs: false,
+        }
+    }
+
+    /// Use `--{name}` for the input flag instead of `--input`.
+    pub fn input_long(mut self, name: &'static str) -> Self {
+        self.input_long = name;
+        self
+    }
+
+    /// Add a `-{c}` short flag for the input.
+    pub fn input_short(mut self, c: char) -> Self {
+        self.input_short = Some(c);
+        self
+    }
+
+    /// Use `--{name}` for the output flag instead of `--output`.
+    pub fn output_long(mut self, name: &'static str) -> Self {
+        self.output_long = name;
+        self
+    }
+
+    /// Add a `-{c}` short flag for the output.
+    pub fn output_short(mut self, c: char) -> Self {
+        self.output_short = Some(c);
+        self
+    }
+
+    /// Treat `sentinel` as stdin and stdout instead of `-`.
+    pub fn stdio(mut self, sentinel: impl Into<OsString>) -> Self {
+        let sentinel = sentinel.into();
+        self.input_parser = self.input_parser.stdio(sentinel.clone());
+        self.output_parser = self.output_parser.stdio(sentinel);
+        self
+    }
+
+    /// Don't treat any argument as stdin or stdout other than `<stdin>` and `<stdout>`.
+    pub fn no_stdio(mut self) -> Self {
+        self.input_parser = self.input_parser.no_stdio();
+        self.output_parser = self.output_parser.no_stdio();
+        self
+    }
+
+    /// Append to an existing output file instead of truncating it.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Create missing parent directories of the output file.
+    pub fn create_dirs(mut self, create_dirs: bool) -> Self {
+        self.create_dirs = create_dirs;
+        self
+    }
+
+    /// Add the input and output flags to `cmd`.
+    pub fn augment_args(&self, cmd: Command) -> Command {
+        let mut input = Arg::new(self.input_long)
+            .long(self.input_long)
+            .value_name("FILE")
+            .value_hint(ValueHint::FilePath)
+            .value_parser(self.input_parser.clone())
+            .help("Input file path [default: stdin]");
+        if let Some(c) = self.input_short {
+            input = input.short(c);
+        }
+        let mut output = Arg::new(self.output_long)
+            .long(self.output_long)
+            .value_name("FILE")
+            .value_hint(ValueHint::FilePath)
+            .value_parser(self.output_parser.clone())
+            .help("Output file path [default: stdout]");
+        if let Some(c) = self.output_short {
+            output = output.short(c);
+        }
+        cmd.arg(input).arg(output)
+    }
+
+    /// Get the input and output parsed from flags added by [`InputOutputBuilder::augment_args`],
+    /// along with the configured output behavior.
+    pub fn from_arg_matches(&self, matches: &ArgMatches) -> ConfiguredInputOutput {
+        let io = InputOutput {
+            input: matches
+                .get_one::<Input>(self.input_long)
+                .cloned()
+                .unwrap_or_default(),
+            output: matches
+                .get_one::<Output>(self.output_long)
+                .cloned()
+                .unwrap_or_default(),
+        };
+        ConfiguredInputOutput {
+            io,
+            append: self.append,
+            create_dirs: self.create_dirs,
+        }
+    }
+}
+
+impl Default for InputOutputBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An [`InputOutput`] that opens with the output behavior of the [`InputOutputBuilder`] it was
+/// parsed by.
+///
+/// Returned by [`InputOutputBuilder::from_arg_matches`].
+#[derive(Debug)]
+pub struct ConfiguredInputOutput {
+    io: InputOutput,
+    append: bool,
+    create_dirs: bool,
+}
+
+impl ConfiguredInputOutput {
+    /// Get the parsed input and output.
+    pub fn io(&self) -> &InputOutput {
+        &self.io
+    }
+
+    /// Get the parsed input and output, discarding the configured behavior.
+    pub fn into_inner(self) -> InputOutput {
+        self.io
+    }
+
+    /// Open the input and then the output with the configured behavior.
+    ///
+    /// See [`InputOutput::open`] for why the input is opened first.
+    pub fn open(self) -> io::Result<(Box<dyn Read + 'static>, Box<dyn Write + 'static>)> {
+        let options = self.output_options();
+        let input = self.io.input.open()?;
+        if self.create_dirs {
+            self.io.output.create_parent_dirs()?;
+        }
+        let output = self.io.output.open_with(&options)?;
+        Ok((input, output))
+    }
+
+    /// Copy the input to the output opened with the configured behavior, like
+    /// [`InputOutput::copy`].
+    pub fn copy(self) -> io::Result<u64> {
+        let options = self.output_options();
+        self.io.copy_with(&options, self.create_dirs)
+    }
+
+    fn output_options(&self) -> OpenOptions {
+        let mut options = OpenOptions::new();
+        if self.append {
+            options.append(true).create(true);
+        } else {
+            options.write(true).create(true).truncate(true);
+        }
+        options
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn copy_applies_settings() {
+        let dir = std::env::temp_dir().join(format!("clap-io-builder-{}", std::process::id()));
+        let input = dir.join("in.txt");
+        let output = dir.join("new/out.txt");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&input, "more").unwrap();
+        let builder = InputOutputBuilder::new().append(true).create_dirs(true);
+        let cmd = builder.augment_args(Command::new("tool"));
+        for _ in 0..2 {
+            let matches = cmd.clone().get_matches_from([
+                "tool".as_ref(),
+                "--input".as_ref(),
+                input.as_os_str(),
+                "--output".as_ref(),
+                output.as_os_str(),
+            ]);
+            assert_eq!(builder.from_arg_matches(&matches).copy().unwrap(), 4);
+        }
+        assert_eq!(fs::read_to_string(&output).unwrap(), "moremore");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, Write};
+
+/// Read the text on the clipboard.
+pub(crate) fn read() -> io::Result<Vec<u8>> {
+    let text = arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.get_text())
+        .map_err(to_io_error)?;
+    Ok(text.into_bytes())
+}
+
+/// Whether the clipboard text is served by the process that set it, as on X11 and Wayland.
+const HOSTED: bool = cfg!(all(
+    unix,
+    not(any(
+        target_os = "macos",
+        target_os = "android",
+        target_os = "emscripten"
+    ))
+));
+
+/// A writer that collects text to put on the clipboard.
+///
+/// The clipboard is set on every flush, and when the writer is dropped if anything was written
+/// since the last flush. One clipboard handle is kept for the life of the writer, since on
+/// Linux the text is only available while the process that set it still holds a handle.
+pub(crate) struct ClipboardWriter {
+    clipboard: Option<arboard::Clipboard>,
+    buf: Vec<u8>,
+    dirty: bool,
+}
+
+impl ClipboardWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            clipboard: None,
+            buf: Vec::new(),
+            dirty: false,
+        }
+    }
+
+    /// Set the clipboard to the text written so far.
+    ///
+    /// With `wait` on Linux, this blocks until another application replaces the text, so that
+    /// the text outlives the writer.
+    fn set(&mut self, wait: bool) -> io::Result<()> {
+        let text = std::str::from_utf8(&self.buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let clipboard = match &mut self.clipboard {
+            Some(clipboard) => clipboard,
+            None => self
+                .clipboard
+                .insert(arboard::Clipboard::new().map_err(to_io_error)?),
+        };
+        let set = clipboard.set();
+        #[cfg(all(
+            unix,
+            not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+        ))]
+        let set = if wait {
+            arboard::SetExtLinux::wait(set)
+        } else {
+            set
+        };
+        #[cfg(not(all(
+            unix,
+            not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+        )))]
+        let _ = wait;
+        set.text(text).map_err(to_io_error)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Write for ClipboardWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.set(false)
+    }
+}
+
+impl Drop for ClipboardWriter {
+    fn drop(&mut self) {
+        // On Linux the text disappears with the last handle, so serve it until it's replaced.
+        if self.dirty || (HOSTED && self.clipboard.is_some()) {
+            let _ = self.set(true);
+        }
+    }
+}
+
+fn to_io_error(e: arboard::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
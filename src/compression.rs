@@ -0,0 +1,265 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Read, Write},
+    path::Path,
+};
+
+/// A compression format, detected from a file extension or chosen with
+/// [`Output::open_compressed_as`](crate::Output::open_compressed_as).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Bzip2, with the `.bz2` extension.
+    Bzip2,
+    /// Gzip, with the `.gz` extension.
+    Gzip,
+    /// Xz, with the `.xz` extension.
+    Xz,
+    /// Zstandard, with the `.zst` extension.
+    Zstd,
+}
+
+impl Compression {
+    /// Detect the compression format from the extension of `path`.
+    pub(crate) fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        if extension.eq_ignore_ascii_case("bz2") {
+            Some(Self::Bzip2)
+        } else if extension.eq_ignore_ascii_case("gz") {
+            Some(Self::Gzip)
+        } else if extension.eq_ignore_ascii_case("xz") {
+            Some(Self::Xz)
+        } else if extension.eq_ignore_ascii_case("zst") {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Wrap `reader` in a decoder for this format.
+    pub(crate) fn decoder(
+        self,
+        reader: Box<dyn Read + 'static>,
+    ) -> io::Result<Box<dyn Read + 'static>> {
+        Ok(match self {
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+
+    /// Prepare an encoder for this format at `level`, failing with
+    /// [`io::ErrorKind::InvalidInput`] if the level is out of range for the format.
+    ///
+    /// Uses each format's default level if `level` is `None`. This is checked before the
+    /// output is opened, so an invalid level doesn't truncate the output file.
+    pub(crate) fn encoder(self, level: Option<u32>) -> io::Result<Encoder> {
+        let invalid = |level: u32| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid {:?} compression level {}", self, level),
+            )
+        };
+        Ok(match self {
+            Self::Bzip2 => Encoder::Bzip2(match level {
+                Some(level) => bzip2::Compression::try_new(level).ok_or_else(|| invalid(level))?,
+                None => bzip2::Compression::default(),
+            }),
+            Self::Gzip => Encoder::Gzip(match level {
+                Some(level @ 0..=9) => flate2::Compression::new(level),
+                Some(level) => return Err(invalid(level)),
+                None => flate2::Compression::default(),
+            }),
+            Self::Xz => {
+                let preset = level.unwrap_or(6);
+                let check = xz2::stream::Check::Crc64;
+                let stream = xz2::stream::Stream::new_easy_encoder(preset, check)
+                    .map_err(|_| invalid(preset))?;
+                Encoder::Xz(stream)
+            }
+            Self::Zstd => Encoder::Zstd(match level {
+                Some(level) => i32::try_from(level)
+                    .ok()
+                    .filter(|level| zstd::compression_level_range().contains(level))
+                    .ok_or_else(|| invalid(level))?,
+                None => 0,
+            }),
+        })
+    }
+}
+
+/// An encoder with a checked compression level, from [`Compression::encoder`].
+pub(crate) enum Encoder {
+    Bzip2(bzip2::Compression),
+    Gzip(flate2::Compression),
+    Xz(xz2::stream::Stream),
+    Zstd(i32),
+}
+
+impl Encoder {
+    /// Wrap `writer` in the encoder.
+    pub(crate) fn wrap(self, writer: Box<dyn Write + 'static>) -> io::Result<CompressedOutput> {
+        let inner = match self {
+            Self::Bzip2(level) => Encoding::Bzip2(bzip2::write::BzEncoder::new(writer, level)),
+            Self::Gzip(level) => Encoding::Gzip(flate2::write::GzEncoder::new(writer, level)),
+            Self::Xz(stream) => Encoding::Xz(xz2::write::XzEncoder::new_stream(writer, stream)),
+            Self::Zstd(level) => Encoding::Zstd(zstd::Encoder::new(writer, level)?),
+        };
+        Ok(CompressedOutput { inner: Some(inner) })
+    }
+}
+
+/// A compressed output stream, returned by
+/// [`Output::open_compressed`](crate::Output::open_compressed) and the other `open_compressed`
+/// methods.
+///
+/// The compressed stream is finished when the writer is dropped, but any error while writing
+/// its end is ignored, leaving a truncated file. Call [`CompressedOutput::finish`] to observe
+/// the error.
+pub struct CompressedOutput {
+    inner: Option<Encoding>,
+}
+
+enum Encoding {
+    Plain(Box<dyn Write + 'static>),
+    Bzip2(bzip2::write::BzEncoder<Box<dyn Write + 'static>>),
+    Gzip(flate2::write::GzEncoder<Box<dyn Write + 'static>>),
+    Xz(xz2::write::XzEncoder<Box<dyn Write + 'static>>),
+    Zstd(zstd::Encoder<'static, Box<dyn Write + 'static>>),
+}
+
+impl CompressedOutput {
+    /// Write an output as is, for an output with no compression format.
+    pub(crate) fn plain(writer: Box<dyn Write + 'static>) -> Self {
+        Self {
+            inner: Some(Encoding::Plain(writer)),
+        }
+    }
+
+    /// Finish the compressed stream and flush the underlying stream, returning any error.
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.inner.take() {
+            Some(inner) => finish(inner),
+            None => Ok(()),
+        }
+    }
+
+    fn get_mut(&mut self) -> &mut dyn Write {
+        match self.inner.as_mut().expect("only taken when finished") {
+            Encoding::Plain(writer) => writer,
+            Encoding::Bzip2(writer) => writer,
+            Encoding::Gzip(writer) => writer,
+            Encoding::Xz(writer) => writer,
+            Encoding::Zstd(writer) => writer,
+        }
+    }
+}
+
+fn finish(inner: Encoding) -> io::Result<()> {
+    let mut writer = match inner {
+        Encoding::Plain(writer) => writer,
+        Encoding::Bzip2(writer) => writer.finish()?,
+        Encoding::Gzip(writer) => writer.finish()?,
+        Encoding::Xz(writer) => writer.finish()?,
+        Encoding::Zstd(writer) => writer.finish()?,
+    };
+    writer.flush()
+}
+
+impl Write for CompressedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.get_mut().flush()
+    }
+}
+
+impl Drop for CompressedOutput {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            let _ = finish(inner);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::Failing, Output};
+
+    #[test]
+    fn invalid_levels() {
+        for (compression, level) in [
+            (Compression::Bzip2, 0),
+            (Compression::Bzip2, 10),
+            (Compression::Gzip, 10),
+            (Compression::Xz, 10),
+            (Compression::Zstd, 1000),
+        ] {
+            let e = compression.encoder(Some(level)).err().unwrap();
+            assert_eq!(e.kind(), io::ErrorKind::InvalidInput, "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn valid_levels() {
+        for compression in [
+            Compression::Bzip2,
+            Compression::Gzip,
+            Compression::Xz,
+            Compression::Zstd,
+        ] {
+            compression.encoder(None).unwrap();
+            compression.encoder(Some(9)).unwrap();
+        }
+    }
+
+    #[test]
+    fn finish_round_trip() {
+        for compression in [
+            Compression::Bzip2,
+            Compression::Gzip,
+            Compression::Xz,
+            Compression::Zstd,
+        ] {
+            let (output, handle) = Output::in_memory();
+            let mut writer = output.open_compressed_as(Some(compression)).unwrap();
+            writer.write_all(b"data").unwrap();
+            writer.finish().unwrap();
+            let mut data = Vec::new();
+            compression
+                .decoder(Box::new(io::Cursor::new(handle.contents())))
+                .unwrap()
+                .read_to_end(&mut data)
+                .unwrap();
+            assert_eq!(data, b"data", "{:?}", compression);
+        }
+    }
+
+    #[test]
+    fn finish_reports_errors() {
+        let output = Output::to_writer(Failing(io::ErrorKind::Other));
+        let writer = output.open_compressed_as(Some(Compression::Gzip)).unwrap();
+        assert_eq!(writer.finish().unwrap_err().kind(), io::ErrorKind::Other);
+    }
+}
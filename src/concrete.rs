@@ -0,0 +1,140 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// An opened [`Input`](crate::Input), returned by
+/// [`Input::open_enum`](crate::Input::open_enum).
+pub enum InputReader {
+    /// A file or file descriptor.
+    File(File),
+    /// Locked stdin.
+    Stdin(StdinReader),
+    /// Any other reader: one from [`Input::from_reader`](crate::Input::from_reader) or
+    /// [`Input::from_bytes`](crate::Input::from_bytes), the response body of a URL, or the text
+    /// read from the clipboard.
+    Reader(Box<dyn Read + Send>),
+}
+
+impl Read for InputReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.read(buf),
+            Self::Stdin(stdin) => stdin.read(buf),
+            Self::Reader(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Whether a [`StdinReader`] is open, so a second one can be refused.
+static STDIN_OPEN: AtomicBool = AtomicBool::new(false);
+
+/// Locked stdin, which can be opened again once this is dropped.
+///
+/// Locking stdin twice on the same thread deadlocks, so while a reader is open, opening stdin
+/// again fails instead.
+pub struct StdinReader {
+    lock: io::StdinLock<'static>,
+}
+
+impl StdinReader {
+    pub(crate) fn lock() -> io::Result<Self> {
+        if STDIN_OPEN.swap(true, Ordering::SeqCst) {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Stdin is already open",
+            ));
+        }
+        Ok(Self {
+            lock: io::stdin().lock(),
+        })
+    }
+
+    /// Get a reference to the stdin lock.
+    pub fn get_ref(&self) -> &io::StdinLock<'static> {
+        &self.lock
+    }
+
+    /// Get a mutable reference to the stdin lock.
+    pub fn get_mut(&mut self) -> &mut io::StdinLock<'static> {
+        &mut self.lock
+    }
+}
+
+impl Read for StdinReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lock.read(buf)
+    }
+}
+
+impl BufRead for StdinReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.lock.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.lock.consume(amt)
+    }
+}
+
+impl Drop for StdinReader {
+    fn drop(&mut self) {
+        STDIN_OPEN.store(false, Ordering::SeqCst);
+    }
+}
+
+/// An opened [`Output`](crate::Output), returned by
+/// [`Output::open_enum`](crate::Output::open_enum).
+pub enum OutputWriter {
+    /// A file or file descriptor.
+    File(File),
+    /// Locked stdout.
+    Stdout(io::StdoutLock<'static>),
+    /// Locked stderr.
+    Stderr(io::StderrLock<'static>),
+    /// Any other writer: one from [`Output::to_writer`](crate::Output::to_writer) or
+    /// [`Output::in_memory`](crate::Output::in_memory), the writer of
+    /// [`Output::sink`](crate::Output::sink) that discards everything, or a writer that sets
+    /// the clipboard.
+    Writer(Box<dyn Write + Send>),
+}
+
+impl Write for OutputWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::File(file) => file.write(buf),
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::Stderr(stderr) => stderr.write(buf),
+            Self::Writer(writer) => writer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::File(file) => file.flush(),
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::Stderr(stderr) => stderr.flush(),
+            Self::Writer(writer) => writer.flush(),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+/// A file format recognized from the leading bytes of an input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ContentKind {
+    /// Bzip2 compressed data.
+    Bzip2,
+    /// A GIF image.
+    Gif,
+    /// Gzip compressed data.
+    Gzip,
+    /// A JPEG image.
+    Jpeg,
+    /// JSON text starting with an object or array.
+    Json,
+    /// A PDF document.
+    Pdf,
+    /// A PNG image.
+    Png,
+    /// Xz compressed data.
+    Xz,
+    /// A zip archive.
+    Zip,
+    /// Zstandard compressed data.
+    Zstd,
+}
+
+const MAGIC: &[(&[u8], ContentKind)] = &[
+    (b"BZh", ContentKind::Bzip2),
+    (b"GIF87a", ContentKind::Gif),
+    (b"GIF89a", ContentKind::Gif),
+    (b"\x1F\x8B", ContentKind::Gzip),
+    (b"\xFF\xD8\xFF", ContentKind::Jpeg),
+    (b"%PDF-", ContentKind::Pdf),
+    (b"\x89PNG\r\n\x1A\n", ContentKind::Png),
+    (b"\xFD7zXZ\0", ContentKind::Xz),
+    (b"PK\x03\x04", ContentKind::Zip),
+    (b"PK\x05\x06", ContentKind::Zip),
+    (b"\x28\xB5\x2F\xFD", ContentKind::Zstd),
+];
+
+impl ContentKind {
+    /// The number of leading bytes to read for [`ContentKind::detect`].
+    pub const PEEK_LEN: usize = 512;
+
+    /// Recognize a format from the leading bytes of an input.
+    ///
+    /// JSON is recognized by its first character after any whitespace or byte order mark.
+    /// Returns `None` if the format isn't known.
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if let Some((_, kind)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+            return Some(*kind);
+        }
+        let text = head.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(head);
+        match text.iter().find(|b| !b.is_ascii_whitespace()) {
+            Some(b'{' | b'[') => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
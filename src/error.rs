@@ -0,0 +1,150 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{borrow::Cow, error::Error, fmt, io, path::Path};
+
+use crate::Stream;
+
+/// An error opening an input or output stream.
+///
+/// Converts into an [`io::Error`] with the same [`io::ErrorKind`] for use with `?` in functions
+/// returning [`io::Result`].
+#[derive(Debug)]
+pub struct OpenError {
+    input: bool,
+    stream: Stream,
+    source: io::Error,
+}
+
+impl OpenError {
+    pub(crate) fn input(stream: &Stream, source: io::Error) -> Self {
+        Self {
+            input: true,
+            stream: stream.clone(),
+            source,
+        }
+    }
+
+    pub(crate) fn output(stream: &Stream, source: io::Error) -> Self {
+        Self {
+            input: false,
+            stream: stream.clone(),
+            source,
+        }
+    }
+
+    /// If the stream is a file get the path.
+    pub fn path(&self) -> Option<&Path> {
+        self.stream.path()
+    }
+
+    /// Get the name of the stream, as displayed by [`Input::name`](crate::Input::name) or
+    /// [`Output::name`](crate::Output::name).
+    pub fn name(&self) -> Cow<'_, str> {
+        self.stream.name()
+    }
+
+    /// Get the kind of the underlying error.
+    pub fn kind(&self) -> io::ErrorKind {
+        self.source.kind()
+    }
+
+    /// Was this error opening an input?
+    pub fn is_input(&self) -> bool {
+        self.input
+    }
+
+    /// Was this error opening an output?
+    pub fn is_output(&self) -> bool {
+        !self.input
+    }
+
+    /// Get the underlying error.
+    pub fn into_source(self) -> io::Error {
+        self.source
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = if self.input { "input" } else { "output" };
+        let kind = if self.stream.path().is_some() || self.stream.is_fd() {
+            " file"
+        } else {
+            ""
+        };
+        write!(
+            f,
+            "Failed to open {}{} `{}`. Cause: {}",
+            direction,
+            kind,
+            self.stream.name(),
+            self.source
+        )
+    }
+}
+
+impl Error for OpenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+impl From<OpenError> for io::Error {
+    fn from(e: OpenError) -> Self {
+        io::Error::new(e.kind(), e)
+    }
+}
+
+/// An error getting the path of an input or output that isn't a file.
+///
+/// Returned by [`Input::try_path`](crate::Input::try_path) and
+/// [`Output::try_path`](crate::Output::try_path).
+#[derive(Debug, Clone)]
+pub struct NotAFileError {
+    stream: Stream,
+}
+
+impl NotAFileError {
+    pub(crate) fn new(stream: &Stream) -> Self {
+        Self {
+            stream: stream.clone(),
+        }
+    }
+
+    /// Get the name of the stream, as displayed by [`Input::name`](crate::Input::name) or
+    /// [`Output::name`](crate::Output::name).
+    pub fn name(&self) -> Cow<'_, str> {
+        self.stream.name()
+    }
+}
+
+impl fmt::Display for NotAFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` is not a file", self.stream.name())
+    }
+}
+
+impl Error for NotAFileError {}
+
+impl From<NotAFileError> for io::Error {
+    fn from(e: NotAFileError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
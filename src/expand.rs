@@ -0,0 +1,135 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    borrow::Cow,
+    env,
+    ffi::OsString,
+    io,
+    path::{self, Path},
+};
+
+#[cfg(windows)]
+const HOME: &str = "USERPROFILE";
+#[cfg(not(windows))]
+const HOME: &str = "HOME";
+
+/// Expand a leading `~` to the home directory and `$VAR` or `${VAR}` to the value of the
+/// environment variable.
+///
+/// `~user` and a `$` that isn't followed by a variable name are left as they are, as are paths
+/// that aren't valid UTF-8. Fails with [`io::ErrorKind::InvalidInput`] if a variable isn't set.
+pub(crate) fn expand(path: &Path) -> io::Result<Cow<'_, Path>> {
+    expand_with(path, |name| env::var_os(name))
+}
+
+fn expand_with(path: &Path, var: impl Fn(&str) -> Option<OsString>) -> io::Result<Cow<'_, Path>> {
+    let Some(mut rest) = path.to_str() else {
+        return Ok(Cow::Borrowed(path));
+    };
+    if !rest.starts_with('~') && !rest.contains('$') {
+        return Ok(Cow::Borrowed(path));
+    }
+    let lookup = |name: &str| {
+        var(name).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("environment variable `{}` isn't set", name),
+            )
+        })
+    };
+    let mut expanded = OsString::new();
+    if let Some(after) = rest.strip_prefix('~') {
+        if after.is_empty() || after.starts_with(path::is_separator) {
+            expanded.push(lookup(HOME)?);
+            rest = after;
+        }
+    }
+    while let Some(start) = rest.find('$') {
+        expanded.push(&rest[..start]);
+        let after = &rest[start + 1..];
+        let (name, next) = if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "unterminated `${` in path")
+            })?;
+            (&braced[..end], &braced[end + 1..])
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+        if is_name(name) {
+            expanded.push(lookup(name)?);
+        } else {
+            expanded.push(&rest[start..rest.len() - next.len()]);
+        }
+        rest = next;
+    }
+    expanded.push(rest);
+    Ok(Cow::Owned(expanded.into()))
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(path: &str) -> io::Result<String> {
+        let var = |name: &str| match name {
+            "HOME" | "USERPROFILE" => Some("/home/me".into()),
+            "DIR" => Some("data".into()),
+            _ => None,
+        };
+        let expanded = expand_with(Path::new(path), var)?;
+        Ok(expanded.to_str().unwrap().to_owned())
+    }
+
+    #[test]
+    fn tilde() {
+        assert_eq!(expand("~").unwrap(), "/home/me");
+        assert_eq!(expand("~/in.txt").unwrap(), "/home/me/in.txt");
+        assert_eq!(expand("~user/in.txt").unwrap(), "~user/in.txt");
+        assert_eq!(expand("a/~/in.txt").unwrap(), "a/~/in.txt");
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(expand("$DIR/in.txt").unwrap(), "data/in.txt");
+        assert_eq!(expand("${DIR}in.txt").unwrap(), "datain.txt");
+        assert_eq!(expand("~/$DIR").unwrap(), "/home/me/data");
+        assert_eq!(expand("a$/b$1/${}").unwrap(), "a$/b$1/${}");
+    }
+
+    #[test]
+    fn unset_variable() {
+        let e = expand("$MISSING/in.txt").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(e.to_string(), "environment variable `MISSING` isn't set");
+        let e = expand("${DIR").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,64 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Read},
+    sync::{Arc, Mutex},
+};
+
+use digest::Digest;
+
+/// A handle to the digest of every byte read from a hashing reader.
+///
+/// See [`Input::open_hashing`](crate::Input::open_hashing).
+#[derive(Debug)]
+pub struct HashHandle<D>(Arc<Mutex<D>>);
+
+impl<D: Digest + Clone> HashHandle<D> {
+    /// Get the digest of the bytes read so far.
+    ///
+    /// The digest only covers the whole input once the reader has reached EOF.
+    pub fn finalize(self) -> digest::Output<D> {
+        let digest = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        digest.clone().finalize()
+    }
+}
+
+/// A reader that updates a digest with every byte read.
+pub(crate) struct HashingReader<R, D> {
+    inner: R,
+    digest: Arc<Mutex<D>>,
+}
+
+impl<R: Read, D: Digest> HashingReader<R, D> {
+    pub(crate) fn new(inner: R) -> (Self, HashHandle<D>) {
+        let digest = Arc::new(Mutex::new(D::new()));
+        let handle = HashHandle(Arc::clone(&digest));
+        (Self { inner, digest }, handle)
+    }
+}
+
+impl<R: Read, D: Digest> Read for HashingReader<R, D> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let mut digest = self.digest.lock().unwrap_or_else(|e| e.into_inner());
+        digest.update(&buf[..n]);
+        Ok(n)
+    }
+}
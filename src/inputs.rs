@@ -0,0 +1,155 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Read},
+    vec,
+};
+
+use clap::{Args, ValueHint};
+
+use crate::Input;
+
+/// Multiple inputs, read one after another like `cat`.
+///
+/// With no inputs, stdin is read.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Args)]
+pub struct Inputs {
+    /// Input file paths
+    #[arg(value_hint = ValueHint::FilePath)]
+    pub inputs: Vec<Input>,
+}
+
+impl Inputs {
+    /// Open the inputs as a single stream.
+    ///
+    /// The first input is opened immediately, and each following input is opened once the
+    /// previous one reaches EOF. An error opening a later input is returned from `read`.
+    pub fn open(self) -> io::Result<Box<dyn Read + 'static>> {
+        let mut inputs = self.inputs;
+        if inputs.is_empty() {
+            inputs.push(Input::default());
+        }
+        let mut rest = inputs.into_iter();
+        let current = rest.next().map(Input::open).transpose()?;
+        Ok(Box::new(Concat { current, rest }))
+    }
+
+    /// Expand a glob pattern like `logs/*.txt` into the matching file inputs, in path order.
+    ///
+    /// The stdio sentinels are parsed as usual before any glob matching, so `-` is still
+    /// stdin. Fails with [`io::ErrorKind::NotFound`] if the pattern matches no files.
+    #[cfg(feature = "glob")]
+    pub fn from_glob(pattern: &str) -> io::Result<Self> {
+        let input = Input::from(std::ffi::OsStr::new(pattern));
+        if !input.is_file() {
+            return Ok(Self::from(vec![input]));
+        }
+        let paths = glob::glob(pattern).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid glob pattern `{}`. Cause: {}", pattern, e),
+            )
+        })?;
+        let inputs = paths
+            .map(|path| path.map(Input::from_path).map_err(io::Error::from))
+            .collect::<io::Result<Vec<_>>>()?;
+        if inputs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No files match the glob pattern `{}`", pattern),
+            ));
+        }
+        Ok(Self::from(inputs))
+    }
+}
+
+impl From<Vec<Input>> for Inputs {
+    fn from(inputs: Vec<Input>) -> Self {
+        Self { inputs }
+    }
+}
+
+/// A reader over each input in turn.
+struct Concat {
+    current: Option<Box<dyn Read + 'static>>,
+    rest: vec::IntoIter<Input>,
+}
+
+impl Read for Concat {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            match &mut self.current {
+                Some(reader) => match reader.read(buf)? {
+                    0 if !buf.is_empty() => self.current = None,
+                    n => return Ok(n),
+                },
+                None => match self.rest.next() {
+                    Some(input) => self.current = Some(input.open()?),
+                    None => return Ok(0),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read_all, OneByte};
+
+    fn inputs(parts: &[&str]) -> Inputs {
+        Inputs::from(
+            parts
+                .iter()
+                .map(|s| Input::from_string(s.to_string()))
+                .collect::<Vec<_>>(),
+        )
+    }
+
+    #[test]
+    fn reads_stop_at_each_boundary() {
+        let mut reader = inputs(&["abc", "", "de"]).open().unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"abc");
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(&buf[..2], b"de");
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn short_reads_concatenate() {
+        let reader = inputs(&["ab", "c", "", "d"]).open().unwrap();
+        assert_eq!(read_all(OneByte(reader)), b"abcd");
+    }
+
+    #[test]
+    fn later_open_error_is_returned_from_read() {
+        let inputs = Inputs::from(vec![
+            Input::from_string("a".to_string()),
+            Input::from_path("does/not/exist"),
+        ]);
+        let mut reader = inputs.open().unwrap();
+        let mut buf = [0; 8];
+        assert_eq!(reader.read(&mut buf).unwrap(), 1);
+        let e = reader.read(&mut buf).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::NotFound);
+    }
+}
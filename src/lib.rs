@@ -35,20 +35,22 @@
 //! }
 //!
 //! let cli = Cli::parse();
-//! let mut input = cli.io.input.open().unwrap();
-//! let mut output = cli.io.output.open().unwrap();
+//! let (mut input, mut output) = cli.io.open().unwrap();
 //! std::io::copy(&mut input, &mut output).unwrap();
 //! ```
 //!
+//! When opening the input and output separately, open the input first. Opening an output
+//! file truncates it, which would lose its contents if the input then failed to open.
+//!
 //! Add just one:
 //!
 //! ```rust,no_run
-//! use clap::Parser;
+//! use clap::{Parser, ValueHint};
 //! use clap_io::Input;
 //!
 //! #[derive(Parser)]
 //! struct Cli {
-//!    #[clap(long = "in")]
+//!     #[clap(long = "in", value_hint = ValueHint::FilePath)]
 //!     input: Input,
 //! }
 //!
@@ -56,35 +58,300 @@
 //! eprintln!("is tty? {}", cli.input.is_tty());
 //! eprintln!("path? {:?}", cli.input.path());
 //! ```
+//!
+//! clap can't tell from the type that an [`Input`] or [`Output`] is a path, so give flags you
+//! define yourself `value_hint = ValueHint::FilePath` for shell completions generated by
+//! `clap_complete` to suggest files, as [`InputOutput`] and [`InputOutputBuilder`] do.
+//!
+//! # Standard streams
+//!
+//! An argument of `-` ([`STDIO`]) or `<stdin>` ([`STDIN`]) parses as stdin, and `-` or
+//! `<stdout>` ([`STDOUT`]) parses as stdout. An output of `<stderr>` ([`STDERR`]) parses as
+//! stderr. These exact strings are checked before anything else, so `<stdout>` is always
+//! stdout even if a file with that name exists. Every other argument is a file path, so a
+//! file literally named `-` or `<stdout>` can be opened by passing `./-` or `./<stdout>`.
+//!
+//! The `-` sentinel is configurable: parse with [`InputParser::stdio`] or
+//! [`OutputParser::stdio`] to use a different string, or with [`InputParser::no_stdio`] or
+//! [`OutputParser::no_stdio`] to never treat `-` as a standard stream. The `<stdin>`,
+//! `<stdout>` and `<stderr>` names are always recognized. In code, construct the value
+//! explicitly with [`Input::stdin`], [`Output::stdout`] or [`Output::stderr`], or with
+//! [`Input::from_path`] or [`Output::from_path`] to bypass the sentinels entirely.
+//!
+//! With the `clipboard` feature, `<clipboard>` parses as the system clipboard for both an
+//! input and an output. The clipboard isn't a stream, so an input reads all of its text when
+//! opened, and an output sets the clipboard to everything written on every flush and when the
+//! writer is dropped. The text must be valid UTF-8. On Linux, X11 and Wayland only keep the
+//! text available while the process that set it is running, so dropping the writer blocks
+//! until another application replaces the clipboard contents.
+//!
+//! An output of `<null>` ([`NULL`]) discards everything written to it without touching the
+//! disk, like `/dev/null` on every platform, for example for a dry run.
+//!
+//! Standard streams are always read and written in binary mode, so bytes pass through
+//! unchanged on every platform. Rust's standard library doesn't translate line endings on
+//! stdin or stdout, even on Windows, so no mode needs to be set. Use
+//! [`Input::open_normalized`] or [`Output::open_normalized`] to convert line endings
+//! explicitly.
+//!
+//! With the `tracing` feature, every method that returns a boxed reader or writer emits a
+//! debug event when the stream is opened and another when it's dropped, as does
+//! [`InputOutput::copy`]. Methods that return the underlying stream, such as `open_enum`,
+//! `open_file`, `open_stdin` and `open_async`, aren't traced.
 
 use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::HashMap,
     ffi::{OsStr, OsString},
     fmt,
-    fs::File,
-    io::{self, Read, Write},
+    fs::{self, File, OpenOptions},
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, Write},
+    mem,
     path::{Path, PathBuf},
     str::FromStr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64},
+        Arc, Mutex,
+    },
 };
 
 use clap::{Args, ValueHint};
 
-const STDIO: &str = "-";
-const STDIN: &str = "<stdin>";
-const STDOUT: &str = "<stdout>";
+mod bom;
+mod buffered;
+mod builder;
+#[cfg(feature = "clipboard")]
+mod clipboard;
+#[cfg(feature = "compression")]
+mod compression;
+mod concrete;
+mod content;
+mod count;
+mod error;
+mod expand;
+#[cfg(feature = "digest")]
+mod hash;
+#[cfg(feature = "http")]
+mod http;
+mod inputs;
+mod kind;
+mod limit;
+mod line_ending;
+mod memory;
+mod parser;
+mod pipe;
+mod retry;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod tee;
+#[cfg(test)]
+mod test_util;
+mod throttle;
+#[cfg(feature = "tracing")]
+mod trace;
+
+pub use buffered::{BufferedOutput, SyncedOutput};
+pub use builder::{ConfiguredInputOutput, InputOutputBuilder};
+#[cfg(feature = "compression")]
+pub use compression::{CompressedOutput, Compression};
+pub use concrete::{InputReader, OutputWriter, StdinReader};
+pub use content::ContentKind;
+pub use error::{NotAFileError, OpenError};
+#[cfg(feature = "digest")]
+pub use hash::HashHandle;
+pub use inputs::Inputs;
+pub use kind::StreamKind;
+pub use line_ending::LineEnding;
+pub use memory::MemoryHandle;
+pub use parser::{InputParser, OutputParser};
+pub use pipe::PipeOutput;
+pub use retry::RetryPolicy;
+
+/// The argument parsed as stdin for an [`Input`] or stdout for an [`Output`].
+pub const STDIO: &str = "-";
+/// The name displayed for stdin, which is also parsed as stdin.
+pub const STDIN: &str = "<stdin>";
+/// The name displayed for stdout, which is also parsed as stdout.
+pub const STDOUT: &str = "<stdout>";
+/// The name displayed for stderr, which is also parsed as stderr for an [`Output`].
+pub const STDERR: &str = "<stderr>";
+/// The argument parsed as the system clipboard for an [`Input`] or [`Output`].
+#[cfg(feature = "clipboard")]
+pub const CLIPBOARD: &str = "<clipboard>";
+/// The argument parsed as an [`Output`] that discards everything written to it.
+pub const NULL: &str = "<null>";
+const FD_PREFIX: &str = "/dev/fd/";
+const READER: &str = "<reader>";
+const WRITER: &str = "<writer>";
 
 /// Combined input and output options.
 #[derive(Debug, Args)]
 pub struct InputOutput {
-    /// Input file path
+    /// Input file path [default: stdin]
+    #[arg(
+        value_name = "FILE",
+        hide_default_value = true,
+        long,
+        default_value_os_t,
+        value_hint = ValueHint::FilePath,
+    )]
+    pub input: Input,
+
+    /// Output file path [default: stdout]
+    #[arg(
+        value_name = "FILE",
+        hide_default_value = true,
+        long,
+        default_value_os_t,
+        value_hint = ValueHint::FilePath,
+    )]
+    pub output: Output,
+}
+
+impl InputOutput {
+    /// Open the input and then the output.
+    ///
+    /// Opening an output file truncates it, so opening the output first would destroy its
+    /// contents even when the input then fails to open, for example because of a typo in the
+    /// input path. Opening both here avoids getting the order wrong.
+    pub fn open(self) -> io::Result<(Box<dyn Read + 'static>, Box<dyn Write + 'static>)> {
+        let input = self.input.open()?;
+        let output = self.output.open()?;
+        Ok((input, output))
+    }
+
+    /// Open the input and then the output and copy the input to the output.
+    ///
+    /// The output is flushed before returning the number of bytes copied.
+    pub fn copy(self) -> io::Result<u64> {
+        self.copy_with(
+            OpenOptions::new().write(true).create(true).truncate(true),
+            false,
+        )
+    }
+
+    fn copy_with(&self, options: &OpenOptions, create_dirs: bool) -> io::Result<u64> {
+        let mut input = self.input.open_enum_ref()?;
+        #[cfg(feature = "tracing")]
+        let _input = self.input.0.opened("input");
+        if create_dirs {
+            self.output.create_parent_dirs()?;
+        }
+        let mut output = self.output.open_enum_with(options)?;
+        #[cfg(feature = "tracing")]
+        let _output = self.output.0.opened("output");
+        copy_opened(&mut input, &mut output)
+    }
+
+    /// Like [`InputOutput::copy`], but first trying to clone an input file into an output file.
+    ///
+    /// On Linux filesystems with copy-on-write support, like Btrfs and XFS, the clone shares
+    /// the input's storage and takes constant time. If cloning isn't supported the input is
+    /// copied as usual. Returns the number of bytes copied and whether the file was cloned.
+    ///
+    /// Cloning is only attempted on Linux, and other platforms always copy. macOS can only
+    /// clone into a path that doesn't exist yet, which would replace the output file instead
+    /// of writing into the one that was opened.
+    pub fn reflink_or_copy(self) -> io::Result<(u64, bool)> {
+        let files = self.input.is_file() && self.output.is_file();
+        let mut input = self.input.open_enum_ref()?;
+        #[cfg(feature = "tracing")]
+        let _input = self.input.0.opened("input");
+        let mut output = self.output.open_enum_ref()?;
+        #[cfg(feature = "tracing")]
+        let _output = self.output.0.opened("output");
+        if let (true, InputReader::File(input), OutputWriter::File(output)) =
+            (files, &input, &output)
+        {
+            if reflink(input, output).is_ok() {
+                return Ok((input.metadata()?.len(), true));
+            }
+        }
+        Ok((copy_opened(&mut input, &mut output)?, false))
+    }
+
+    /// Like [`InputOutput::copy`], but copying through a buffer of `size` bytes.
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] before opening anything if `size` is zero.
+    pub fn copy_with_buffer_size(self, size: usize) -> io::Result<u64> {
+        if size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Copy buffer size must be greater than zero",
+            ));
+        }
+        let (mut input, mut output) = self.open()?;
+        let mut buf = vec![0; size];
+        let mut n = 0;
+        loop {
+            let len = match input.read(&mut buf) {
+                Ok(0) => break,
+                Ok(len) => len,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            output.write_all(&buf[..len])?;
+            n += len as u64;
+        }
+        output.flush()?;
+        Ok(n)
+    }
+
+    /// Are the input and output the same existing file?
+    ///
+    /// Opening the output would truncate the input before it is read, so check this before
+    /// transforming a file in place. Files are compared by device and inode on Unix, and by
+    /// canonical path elsewhere.
+    pub fn same_file(&self) -> bool {
+        match (self.input.path(), self.output.path()) {
+            (Some(input), Some(output)) => same_file(input, output),
+            _ => false,
+        }
+    }
+
+    /// Open the input and then the output, giving an output file the input file's permissions.
+    ///
+    /// Permissions are only copied on Unix when both the input and output are files.
+    pub fn open_preserving_permissions(
+        self,
+    ) -> io::Result<(Box<dyn Read + 'static>, Box<dyn Write + 'static>)> {
+        let mode = match self.input.metadata() {
+            Some(metadata) => file_mode(&metadata?),
+            None => None,
+        };
+        let input = self.input.open()?;
+        let output = match mode {
+            Some(mode) => self.output.open_with_mode(mode)?,
+            None => self.output.open()?,
+        };
+        Ok((input, output))
+    }
+}
+
+/// Combined input and output options, with `-i` and `-o` short flags.
+///
+/// Use [`InputOutput`] instead if `-i` or `-o` already mean something else in your command.
+/// Convert into an [`InputOutput`] to use its methods.
+#[derive(Debug, Args)]
+pub struct InputOutputShort {
+    /// Input file path [default: stdin]
     #[arg(
+        value_name = "FILE",
+        hide_default_value = true,
+        short,
         long,
         default_value_os_t,
         value_hint = ValueHint::FilePath,
     )]
     pub input: Input,
 
-    /// Output file path
+    /// Output file path [default: stdout]
     #[arg(
+        value_name = "FILE",
+        hide_default_value = true,
+        short,
         long,
         default_value_os_t,
         value_hint = ValueHint::FilePath,
@@ -92,203 +359,1651 @@ pub struct InputOutput {
     pub output: Output,
 }
 
+impl From<InputOutputShort> for InputOutput {
+    fn from(io: InputOutputShort) -> Self {
+        Self {
+            input: io.input,
+            output: io.output,
+        }
+    }
+}
+
 /// Either a file or stdin.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Input(Stream);
 
 impl Input {
+    /// An input that reads from the file at `path`.
+    ///
+    /// Unlike parsing, `-` and `<stdin>` are not treated specially.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self(Stream::File(path.into()))
+    }
+
+    /// An input that reads from stdin.
+    pub fn stdin() -> Self {
+        Self(Stream::stdin())
+    }
+
+    /// An input that reads from the inherited file descriptor `fd`, such as for an
+    /// `--input-fd` flag.
+    ///
+    /// Opening duplicates the descriptor, so the original is never closed and the input can be
+    /// opened again. The descriptor must stay open until the input is opened. Descriptor 0 is
+    /// stdin, as when parsing `/dev/fd/0`.
+    #[cfg(unix)]
+    pub fn from_fd(fd: std::os::fd::RawFd) -> Self {
+        match fd {
+            0 => Self::stdin(),
+            fd => Self(Stream::fd(fd)),
+        }
+    }
+
+    /// An input that reads from `reader`.
+    ///
+    /// The reader can only be opened once, including through clones of the input.
+    pub fn from_reader(reader: impl Read + Send + 'static) -> Self {
+        Self(Stream::Reader(Handle::new(Box::new(reader))))
+    }
+
+    /// An input that reads `bytes` from memory, for example as built in sample data.
+    ///
+    /// Like [`Input::from_reader`], it can only be opened once.
+    pub fn from_bytes(bytes: impl Into<Vec<u8>>) -> Self {
+        Self::from_reader(io::Cursor::new(bytes.into()))
+    }
+
+    /// An input that reads the text `s` from memory, like [`Input::from_bytes`].
+    pub fn from_string(s: String) -> Self {
+        Self::from_bytes(s)
+    }
+
     /// Open the input stream.
-    pub fn open(self) -> io::Result<Box<dyn Read + 'static>> {
-        match self.0 {
-            Stream::File(_) => {
+    pub fn open(self) -> Result<Box<dyn Read + 'static>, OpenError> {
+        self.open_ref()
+    }
+
+    /// Fail if the input is an interactive TTY, instead of blocking on a read from the terminal.
+    ///
+    /// Without piped stdin or an input file, this errors with [`io::ErrorKind::InvalidInput`]
+    /// before anything is opened.
+    pub fn require_non_tty(self) -> Result<Self, OpenError> {
+        if self.is_tty() {
+            let e = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "no input provided; pass an input file or pipe data on stdin",
+            );
+            return Err(OpenError::input(&self.0, e));
+        }
+        Ok(self)
+    }
+
+    /// Open the input stream without consuming the input.
+    ///
+    /// This can be called again to reopen the input, for example to retry after a transient
+    /// error. Each call opens a file from the start, while reopening stdin continues from
+    /// wherever the previous reader stopped. Opening stdin fails while a reader from an
+    /// earlier open is still alive, instead of deadlocking on the stdin lock, so drop the
+    /// previous reader first. Use [`Input::open_unlocked`] to read stdin from more than one
+    /// place at once. An input from [`Input::from_reader`] can only be opened once.
+    pub fn open_ref(&self) -> Result<Box<dyn Read + 'static>, OpenError> {
+        let reader: Box<dyn Read + 'static> = match &self.0 {
+            Stream::Reader(handle) => handle.take().map_err(|e| OpenError::input(&self.0, e))?,
+            _ => Box::new(self.open_enum_ref()?),
+        };
+        #[cfg(feature = "tracing")]
+        let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+        Ok(reader)
+    }
+
+    /// Open the input stream, first expanding a leading `~` and any `$VAR` or `${VAR}` in a
+    /// file path.
+    ///
+    /// [`Input::open`] never expands paths, since the shell already has. This is for paths that
+    /// come from elsewhere, such as a config file. `~` expands to the home directory and
+    /// variables to the process environment. Fails with [`io::ErrorKind::InvalidInput`] if a
+    /// variable isn't set.
+    pub fn open_expanded(self) -> Result<Box<dyn Read + 'static>, OpenError> {
+        match &self.0 {
+            Stream::File(path) => {
+                let path = expand::expand(path).map_err(|e| OpenError::input(&self.0, e))?;
+                Input(Stream::File(path.into_owned())).open()
+            }
+            _ => self.open(),
+        }
+    }
+
+    /// Open the input stream, retrying transient errors as configured by `policy`.
+    pub fn open_with_retry(
+        self,
+        policy: RetryPolicy,
+    ) -> Result<Box<dyn Read + 'static>, OpenError> {
+        policy.retry(|| self.open_ref())
+    }
+
+    /// Open the input stream as a concrete [`InputReader`] instead of a boxed reader.
+    ///
+    /// Matching on the reader gives access to the underlying [`File`] or stdin lock.
+    pub fn open_enum(self) -> io::Result<InputReader> {
+        Ok(self.open_enum_ref()?)
+    }
+
+    fn open_enum_ref(&self) -> Result<InputReader, OpenError> {
+        match &self.0 {
+            Stream::File(_) | Stream::Fd { .. } => {
                 let file = self.open_file().unwrap()?;
-                Ok(Box::new(file))
+                Ok(InputReader::File(file))
             }
             Stream::Stdin { .. } => {
-                let stdin = self.open_stdin().unwrap();
-                Ok(Box::new(stdin))
+                let stdin = StdinReader::lock().map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(InputReader::Stdin(stdin))
+            }
+            #[cfg(feature = "http")]
+            Stream::Url(url) => {
+                let reader = http::get(url).map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(InputReader::Reader(reader))
+            }
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => {
+                let text = clipboard::read().map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(InputReader::Reader(Box::new(io::Cursor::new(text))))
+            }
+            Stream::Reader(handle) => {
+                let reader = handle.take().map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(InputReader::Reader(reader))
+            }
+            Stream::Stdout { .. } | Stream::Stderr { .. } | Stream::Null | Stream::Writer(_) => {
+                unreachable!("stdout is an output")
             }
-            Stream::Stdout { .. } => unreachable!("stdout is an output"),
         }
     }
 
-    /// Open the input as stdin.
-    pub fn open_stdin(self) -> Result<io::StdinLock<'static>, Self> {
-        match self.0 {
-            Stream::Stdin { .. } => {
-                let stdin = Box::leak(Box::new(io::stdin()));
-                Ok(stdin.lock())
+    /// Open the input stream with a buffered reader.
+    ///
+    /// Use this for APIs that need a [`BufRead`], like line or record readers. Files are
+    /// wrapped in a [`BufReader`], while stdin is already buffered and its lock is returned
+    /// without an additional buffer.
+    pub fn open_buffered(self) -> io::Result<Box<dyn BufRead + 'static>> {
+        let reader: Box<dyn BufRead + 'static> = match self.open_enum_ref()? {
+            InputReader::Stdin(stdin) => Box::new(stdin),
+            reader => Box::new(BufReader::new(reader)),
+        };
+        #[cfg(feature = "tracing")]
+        let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+        Ok(reader)
+    }
+
+    /// Open the input stream with a buffered reader of the given capacity.
+    pub fn open_buffered_with_capacity(
+        self,
+        capacity: usize,
+    ) -> io::Result<Box<dyn BufRead + 'static>> {
+        let input = self.open()?;
+        Ok(Box::new(BufReader::with_capacity(capacity, input)))
+    }
+
+    /// Open the input stream, skipping a leading UTF-8 byte order mark.
+    ///
+    /// Inputs without a BOM are read unchanged.
+    pub fn open_strip_bom(self) -> io::Result<Box<dyn Read + 'static>> {
+        Ok(Box::new(bom::StripBom::new(self.open()?)))
+    }
+
+    /// Open the input stream, converting every line ending to `ending`.
+    ///
+    /// Converting to [`LineEnding::Lf`] replaces `\r\n` with `\n`, and converting to
+    /// [`LineEnding::CrLf`] replaces a `\n` not already preceded by `\r` with `\r\n`. A lone
+    /// `\r` is left as is.
+    pub fn open_normalized(self, ending: LineEnding) -> io::Result<Box<dyn Read + 'static>> {
+        Ok(Box::new(line_ending::NormalizeReader::new(
+            self.open()?,
+            ending,
+        )))
+    }
+
+    /// Open the input stream with a buffered reader, also checking whether the input is empty.
+    ///
+    /// This works for any input, but necessarily reads the first bytes into the buffer, so it
+    /// blocks until stdin has data or is closed. Nothing is lost, since the bytes are still
+    /// returned by the reader.
+    pub fn open_peek(self) -> io::Result<(Box<dyn BufRead + 'static>, bool)> {
+        let mut input = self.open_buffered()?;
+        let empty = loop {
+            match input.fill_buf() {
+                Ok(buf) => break buf.is_empty(),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
             }
-            _ => Err(self),
-        }
+        };
+        Ok((input, empty))
     }
 
-    /// Open the input as a file.
-    pub fn open_file(&self) -> Option<io::Result<File>> {
-        match &self.0 {
-            Stream::File(path) => match File::open(&path) {
-                Ok(file) => Some(Ok(file)),
-                Err(e) => Some(Err(io::Error::new(
-                    e.kind(),
-                    format!(
-                        "Failed to open input file `{}`. Cause: {}",
-                        path.display(),
-                        e
-                    ),
-                ))),
-            },
-            _ => None,
+    /// Open the input stream, reading up to `n` leading bytes to inspect before processing.
+    ///
+    /// The returned reader yields the leading bytes again before the rest of the input. Fewer
+    /// than `n` bytes are returned only if the input is shorter.
+    pub fn open_peekable(self, n: usize) -> io::Result<(Vec<u8>, Box<dyn Read + 'static>)> {
+        let mut input = self.open()?;
+        let mut head = Vec::with_capacity(n);
+        input.by_ref().take(n as u64).read_to_end(&mut head)?;
+        let reader = io::Cursor::new(head.clone()).chain(input);
+        Ok((head, Box::new(reader)))
+    }
+
+    /// Recognize the format of the input from its leading bytes.
+    ///
+    /// Only a file path is sniffed, by opening it separately from any later open. Reading any
+    /// other input, like stdin, would consume the bytes, so `None` is returned without reading
+    /// anything. To sniff those, use [`Input::open_peekable`] with [`ContentKind::PEEK_LEN`]
+    /// and pass the bytes to [`ContentKind::detect`].
+    pub fn sniff(&self) -> io::Result<Option<ContentKind>> {
+        if !self.is_file() {
+            return Ok(None);
         }
+        let mut head = Vec::with_capacity(ContentKind::PEEK_LEN);
+        self.open_file()
+            .unwrap()?
+            .take(ContentKind::PEEK_LEN as u64)
+            .read_to_end(&mut head)?;
+        Ok(ContentKind::detect(&head))
     }
 
-    /// Is this input a TTY?
-    pub fn is_tty(&self) -> bool {
-        self.0.is_tty()
+    /// Open the input stream with a buffered reader, skipping the first `n` lines.
+    ///
+    /// A final line without a trailing newline counts as a line. An input with fewer than `n`
+    /// lines is read to the end.
+    pub fn open_skipping_lines(self, n: usize) -> io::Result<Box<dyn BufRead + 'static>> {
+        let mut input = self.open_buffered()?;
+        for _ in 0..n {
+            if skip_line(&mut input)? == 0 {
+                break;
+            }
+        }
+        Ok(input)
     }
 
-    /// If the input is a file get the path.
-    pub fn path(&self) -> Option<&Path> {
-        self.0.path()
+    /// Open the input stream and iterate over its lines, without the line endings.
+    ///
+    /// A line that isn't valid UTF-8 is returned as an [`io::ErrorKind::InvalidData`] error.
+    /// See [`Input::lines_lossy`] to replace invalid sequences instead.
+    pub fn lines(self) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+        Ok(self.open_buffered()?.lines())
     }
-}
 
-impl Default for Input {
-    fn default() -> Self {
-        Self(Stream::stdin())
+    /// Open the input stream and iterate over its lines, without the line endings.
+    ///
+    /// Invalid UTF-8 sequences are replaced with `U+FFFD REPLACEMENT CHARACTER`.
+    pub fn lines_lossy(self) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+        let mut reader = self.open_buffered()?;
+        let mut buf = Vec::new();
+        Ok(std::iter::from_fn(move || {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => None,
+                Ok(_) => {
+                    if buf.ends_with(b"\n") {
+                        buf.pop();
+                        if buf.ends_with(b"\r") {
+                            buf.pop();
+                        }
+                    }
+                    Some(Ok(String::from_utf8_lossy(&buf).into_owned()))
+                }
+                Err(e) => Some(Err(e)),
+            }
+        }))
     }
-}
 
-impl fmt::Display for Input {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        self.0.fmt(f)
+    /// Open the input stream, reading every file in a directory one after another.
+    ///
+    /// The regular files directly in the directory are read in lexicographic order of their
+    /// names, skipping subdirectories. An error listing the directory is returned immediately,
+    /// and an error opening one of its files is returned from `read` once the previous file
+    /// has been read, like [`Inputs::open`]. Any input that isn't a directory opens as usual.
+    pub fn open_dir(self) -> io::Result<Box<dyn Read + 'static>> {
+        let dir = match self.path() {
+            Some(path) if path.is_dir() => path,
+            _ => return Ok(self.open()?),
+        };
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).map_err(|e| input_error(&self.0, e))? {
+            let path = entry.map_err(|e| input_error(&self.0, e))?.path();
+            if path.is_file() {
+                paths.push(path);
+            }
+        }
+        if paths.is_empty() {
+            let reader = Box::new(io::empty());
+            #[cfg(feature = "tracing")]
+            let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+            return Ok(reader);
+        }
+        paths.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+        let inputs: Vec<_> = paths.into_iter().map(Input::from_path).collect();
+        Inputs::from(inputs).open()
     }
-}
 
-impl FromStr for Input {
-    type Err = std::convert::Infallible;
+    /// Open the input stream, decompressing it based on the file extension.
+    ///
+    /// Files ending in `.gz`, `.zst`, `.bz2` or `.xz` are decompressed. Any other
+    /// input, including stdin, is read as is.
+    #[cfg(feature = "compression")]
+    pub fn open_decompressed(self) -> io::Result<Box<dyn Read + 'static>> {
+        let compression = self.path().and_then(Compression::from_path);
+        let input = self.open()?;
+        match compression {
+            Some(compression) => compression.decoder(input),
+            None => Ok(input),
+        }
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self::from(s.as_ref()))
+    /// Open the input stream, hashing every byte read with the digest `D`.
+    ///
+    /// The digest returned by [`HashHandle::finalize`] is only complete once the
+    /// reader has reached EOF.
+    #[cfg(feature = "digest")]
+    pub fn open_hashing<D>(self) -> io::Result<(Box<dyn Read + 'static>, HashHandle<D>)>
+    where
+        D: digest::Digest + Send + 'static,
+    {
+        let (reader, handle) = hash::HashingReader::new(self.open()?);
+        Ok((Box::new(reader), handle))
     }
-}
 
-impl From<&OsStr> for Input {
-    fn from(s: &OsStr) -> Self {
-        if s == STDIO || s == STDIN {
-            Self(Stream::stdin())
-        } else {
-            Self(Stream::file(s))
+    /// Open the input stream, advancing `bar` by the bytes read.
+    ///
+    /// The length of the bar is set to the size of an input file. For any other input,
+    /// including stdin, the length is unset so the bar shows progress without a total.
+    #[cfg(feature = "indicatif")]
+    pub fn open_with_progress(
+        self,
+        bar: indicatif::ProgressBar,
+    ) -> io::Result<Box<dyn Read + 'static>> {
+        match self.len() {
+            Some(len) => bar.set_length(len?),
+            None => bar.unset_length(),
         }
+        Ok(Box::new(bar.wrap_read(self.open()?)))
     }
-}
 
-impl From<Input> for OsString {
-    fn from(input: Input) -> Self {
-        input.0.into()
+    /// Open the input stream, counting the bytes read.
+    pub fn open_counting(self) -> io::Result<(Box<dyn Read + 'static>, Arc<AtomicU64>)> {
+        let (reader, count) = count::Counting::new(self.open()?);
+        Ok((Box::new(reader), count))
     }
-}
 
-/// Either a file or stdout.
-#[derive(Debug, Clone)]
-pub struct Output(Stream);
+    /// Open the input stream, reaching EOF after at most `max_bytes`.
+    ///
+    /// The returned flag is set if the reader reached the limit before the end of the
+    /// input, which is checked by reading one byte past the limit.
+    pub fn open_limited(
+        self,
+        max_bytes: u64,
+    ) -> io::Result<(Box<dyn Read + 'static>, Arc<AtomicBool>)> {
+        let (reader, truncated) = limit::Limited::new(self.open()?, max_bytes);
+        Ok((Box::new(reader), truncated))
+    }
 
-impl Output {
-    /// Open the output stream.
-    pub fn open(self) -> io::Result<Box<dyn Write + 'static>> {
+    /// Open the input stream, starting at byte offset `start` and reading at most `len` bytes.
+    ///
+    /// A file or file descriptor is seeked to `start`. If that fails, as for a pipe, or for
+    /// any other input including stdin, the first `start` bytes are read and discarded
+    /// instead, which takes time proportional to `start`.
+    pub fn open_range(self, start: u64, len: Option<u64>) -> io::Result<Box<dyn Read + 'static>> {
+        let input: Box<dyn Read> = match self.open_file() {
+            Some(file) => {
+                let mut file = file?;
+                if file.seek(io::SeekFrom::Start(start)).is_err() {
+                    skip_bytes(&mut file, start).map_err(|e| input_error(&self.0, e))?;
+                }
+                let reader = Box::new(file);
+                #[cfg(feature = "tracing")]
+                let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+                reader
+            }
+            None => {
+                let mut input = self.open()?;
+                skip_bytes(&mut input, start)?;
+                input
+            }
+        };
+        match len {
+            Some(len) => Ok(Box::new(input.take(len))),
+            None => Ok(input),
+        }
+    }
+
+    /// Open the input stream, reading at most `bytes_per_sec` bytes per second.
+    ///
+    /// Reads sleep as needed to stay under the rate, which is averaged over one second.
+    pub fn open_throttled(self, bytes_per_sec: u64) -> io::Result<Box<dyn Read + 'static>> {
+        let rate = throttle::rate(bytes_per_sec)?;
+        Ok(Box::new(throttle::Throttled::new(self.open()?, rate)))
+    }
+
+    /// Memory map an input file.
+    ///
+    /// The map derefs to `&[u8]`. Fails with [`io::ErrorKind::Unsupported`] if the input is
+    /// not a file.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it is mapped, see
+    /// [`memmap2::Mmap::map`].
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap(self) -> io::Result<memmap2::Mmap> {
         match self.0 {
-            Stream::File(_) => {
+            Stream::File(_) | Stream::Fd { .. } => {
                 let file = self.open_file().unwrap()?;
-                Ok(Box::new(file))
+                memmap2::Mmap::map(&file).map_err(|e| input_error(&self.0, e))
             }
-            Stream::Stdout { .. } => {
-                let stdout = self.open_stdout().unwrap();
-                Ok(Box::new(stdout))
-            }
-            Stream::Stdin { .. } => unreachable!("stdin is an input"),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to memory map {}", self.0),
+            )),
         }
     }
 
-    /// Open the output as stdout.
-    pub fn open_stdout(self) -> Result<io::StdoutLock<'static>, Self> {
+    /// Open the input stream as a seekable reader.
+    ///
+    /// Fails with [`io::ErrorKind::Unsupported`] if the input is not a file.
+    pub fn open_seekable(self) -> io::Result<Box<dyn ReadSeek + 'static>> {
         match self.0 {
-            Stream::Stdout { .. } => {
-                let stdout = Box::leak(Box::new(io::stdout()));
-                Ok(stdout.lock())
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file().unwrap()?;
+                let reader = Box::new(file);
+                #[cfg(feature = "tracing")]
+                let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+                Ok(reader)
             }
-            _ => Err(self),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as a seekable input", self.0),
+            )),
         }
     }
 
-    /// Open the output as a file.
-    pub fn open_file(&self) -> Option<io::Result<File>> {
+    /// Open the input stream as a tokio `AsyncRead`.
+    ///
+    /// Stdin isn't locked, so this doesn't check whether stdin is already open.
+    ///
+    /// Only files, file descriptors and stdin can be opened, since tokio has no way to read
+    /// other streams without blocking. Unlike `Input::open_async_futures`, which reads them
+    /// on a background thread pool, a URL, the clipboard or an input made with
+    /// [`Input::from_reader`] fails with [`io::ErrorKind::Unsupported`].
+    #[cfg(feature = "tokio")]
+    pub fn open_async(
+        self,
+    ) -> io::Result<std::pin::Pin<Box<dyn ::tokio::io::AsyncRead + Send + 'static>>> {
         match &self.0 {
-            Stream::File(path) => match File::create(&path) {
-                Ok(file) => Some(Ok(file)),
-                Err(e) => Some(Err(io::Error::new(
-                    e.kind(),
-                    format!(
-                        "Failed to open output file `{}`. Cause: {}",
-                        path.display(),
-                        e
-                    ),
-                ))),
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file().unwrap()?;
+                Ok(Box::pin(::tokio::fs::File::from_std(file)))
+            }
+            Stream::Stdin { .. } => Ok(Box::pin(::tokio::io::stdin())),
+            #[cfg(feature = "http")]
+            Stream::Url(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as an async input", self.0),
+            )),
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as an async input", self.0),
+            )),
+            Stream::Reader(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as an async input", self.0),
+            )),
+            Stream::Stdout { .. } | Stream::Stderr { .. } | Stream::Null | Stream::Writer(_) => {
+                unreachable!("stdout is an output")
+            }
+        }
+    }
+
+    /// Open the input stream as a `futures` `AsyncRead`.
+    ///
+    /// Reads are performed on a background thread pool. Stdin isn't locked, so this doesn't
+    /// check whether stdin is already open.
+    #[cfg(feature = "futures")]
+    pub fn open_async_futures(
+        self,
+    ) -> io::Result<std::pin::Pin<Box<dyn futures_io::AsyncRead + Send + 'static>>> {
+        match &self.0 {
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file().unwrap()?;
+                Ok(Box::pin(blocking::Unblock::new(file)))
+            }
+            Stream::Stdin { .. } => Ok(Box::pin(blocking::Unblock::new(io::stdin()))),
+            #[cfg(feature = "http")]
+            Stream::Url(url) => {
+                let reader = http::get(url).map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(Box::pin(blocking::Unblock::new(reader)))
+            }
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => {
+                let text = clipboard::read().map_err(|e| OpenError::input(&self.0, e))?;
+                Ok(Box::pin(blocking::Unblock::new(io::Cursor::new(text))))
+            }
+            Stream::Reader(handle) => Ok(Box::pin(blocking::Unblock::new(handle.take()?))),
+            Stream::Stdout { .. } | Stream::Stderr { .. } | Stream::Null | Stream::Writer(_) => {
+                unreachable!("stdout is an output")
+            }
+        }
+    }
+
+    /// Open the input stream without holding the stdin lock.
+    ///
+    /// Each read from stdin takes and releases the lock, which is slower than the locked reader
+    /// from [`Input::open`] but lets other code in the process read stdin in between. This
+    /// doesn't check whether stdin is already open. Other inputs open as usual.
+    pub fn open_unlocked(self) -> Result<Box<dyn Read + 'static>, OpenError> {
+        if !self.is_stdin() {
+            return self.open();
+        }
+        let reader = Box::new(io::stdin());
+        #[cfg(feature = "tracing")]
+        let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+        Ok(reader)
+    }
+
+    /// Open the input stream as a reader that can be sent to another thread.
+    ///
+    /// The stdin lock can't be sent between threads, so stdin is opened unlocked as with
+    /// [`Input::open_unlocked`], without checking whether stdin is already open.
+    pub fn open_send(self) -> Result<Box<dyn Read + Send + 'static>, OpenError> {
+        let reader: Box<dyn Read + Send + 'static> = match self.0 {
+            Stream::Stdin { .. } => Box::new(io::stdin()),
+            _ => match self.open_enum_ref()? {
+                InputReader::File(file) => Box::new(file),
+                InputReader::Reader(reader) => reader,
+                InputReader::Stdin(_) => unreachable!("stdin is opened unlocked"),
             },
-            _ => None,
+        };
+        #[cfg(feature = "tracing")]
+        let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+        Ok(reader)
+    }
+
+    /// Open the input stream, holding a shared advisory lock on a file until the reader is
+    /// dropped.
+    ///
+    /// This blocks while another process holds an exclusive lock, such as from
+    /// [`Output::open_locked`]. Other inputs open as usual.
+    #[cfg(feature = "locking")]
+    pub fn open_locked(self) -> io::Result<Box<dyn Read + 'static>> {
+        match self.open_file() {
+            Some(file) => {
+                let file = file?;
+                fs2::FileExt::lock_shared(&file).map_err(|e| input_error(&self.0, e))?;
+                let reader = Box::new(file);
+                #[cfg(feature = "tracing")]
+                let reader = Box::new(trace::Traced::new(reader, self.0.opened("input")));
+                Ok(reader)
+            }
+            None => Ok(self.open()?),
         }
     }
 
-    /// Is this output a TTY?
+    /// Open the input as stdin.
+    ///
+    /// Unlike [`Input::open`], this doesn't check whether stdin is already open, so locking
+    /// stdin again while the returned lock is alive deadlocks.
+    pub fn open_stdin(self) -> Result<io::StdinLock<'static>, Self> {
+        match self.0 {
+            Stream::Stdin { .. } => Ok(io::stdin().lock()),
+            _ => Err(self),
+        }
+    }
+
+    /// Open the input as a file.
+    pub fn open_file(&self) -> Option<Result<File, OpenError>> {
+        let file = match &self.0 {
+            Stream::File(path) => File::open(path),
+            Stream::Fd { fd, .. } => open_fd(*fd),
+            _ => return None,
+        };
+        Some(file.map_err(|e| OpenError::input(&self.0, e)))
+    }
+
+    /// Get the name of the input: the path of a file, or `<stdin>`.
+    pub fn name(&self) -> Cow<'_, str> {
+        self.0.name()
+    }
+
+    /// Display the input, showing stdin as `stdin` instead of `<stdin>`.
+    pub fn display_as<'a>(&'a self, stdin: &'a str) -> DisplayAs<'a> {
+        DisplayAs {
+            stream: &self.0,
+            name: matches!(self.0, Stream::Stdin { .. }).then_some(stdin),
+        }
+    }
+
+    /// Get the kind of stream this input refers to.
+    pub fn kind(&self) -> StreamKind {
+        self.0.kind()
+    }
+
+    /// Is this input stdin?
+    pub fn is_stdin(&self) -> bool {
+        matches!(self.0, Stream::Stdin { .. })
+    }
+
+    /// Is this input a file path?
+    pub fn is_file(&self) -> bool {
+        matches!(self.0, Stream::File(_))
+    }
+
+    /// Is this input a TTY?
+    ///
+    /// The terminal status is queried each time this is called.
     pub fn is_tty(&self) -> bool {
         self.0.is_tty()
     }
 
-    /// If the output is a file get the path.
+    /// Was this input a TTY when it was parsed?
+    pub fn was_tty_at_parse(&self) -> bool {
+        self.0.was_tty()
+    }
+
+    /// If the input is a file get the path.
     pub fn path(&self) -> Option<&Path> {
         self.0.path()
     }
+
+    /// Get the path of the input file, failing if the input isn't a file.
+    pub fn try_path(&self) -> Result<&Path, NotAFileError> {
+        self.0.path().ok_or_else(|| NotAFileError::new(&self.0))
+    }
+
+    /// Get the path of the input file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the input isn't a file.
+    #[track_caller]
+    pub fn expect_path(&self) -> &Path {
+        match self.try_path() {
+            Ok(path) => path,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// If the input is a file get its absolute path, with symlinks and `..` resolved.
+    pub fn canonical_path(&self) -> Option<io::Result<PathBuf>> {
+        let path = self.path()?;
+        Some(fs::canonicalize(path).map_err(|e| input_error(&self.0, e)))
+    }
+
+    /// If the input is a file get its length in bytes.
+    pub fn len(&self) -> Option<io::Result<u64>> {
+        Some(self.metadata()?.map(|metadata| metadata.len()))
+    }
+
+    /// If the input is a file check whether its length is zero.
+    pub fn is_empty(&self) -> Option<io::Result<bool>> {
+        Some(self.len()?.map(|len| len == 0))
+    }
+
+    /// If the input is a file get its metadata.
+    pub fn metadata(&self) -> Option<io::Result<fs::Metadata>> {
+        let path = self.path()?;
+        Some(fs::metadata(path).map_err(|e| input_error(&self.0, e)))
+    }
+
+    /// Check that the input can be opened, without reading from it.
+    ///
+    /// A file is opened for reading and closed again, and fails with
+    /// [`io::ErrorKind::InvalidInput`] if it is a directory. A named pipe is opened without
+    /// waiting for a writer. Other inputs, such as stdin or a URL, are only checked when they
+    /// are opened.
+    pub fn check(&self) -> io::Result<()> {
+        let file = match &self.0 {
+            Stream::File(path) => nonblocking(OpenOptions::new().read(true)).open(path),
+            Stream::Fd { fd, .. } => open_fd(*fd),
+            _ => return Ok(()),
+        };
+        let metadata = file
+            .and_then(|file| file.metadata())
+            .map_err(|e| input_error(&self.0, e))?;
+        if metadata.is_dir() {
+            let e = io::Error::new(io::ErrorKind::InvalidInput, "Is a directory");
+            return Err(input_error(&self.0, e));
+        }
+        Ok(())
+    }
+
+    fn parse(s: &OsStr, stdio: Option<&OsStr>) -> Self {
+        if s == STDIN || Some(s) == stdio {
+            return Self(Stream::stdin());
+        }
+        #[cfg(feature = "clipboard")]
+        if s == CLIPBOARD {
+            return Self(Stream::Clipboard);
+        }
+        #[cfg(feature = "http")]
+        if let Some(url) = s.to_str().filter(|s| http::is_url(s)) {
+            return Self(Stream::Url(url.to_owned()));
+        }
+        match fd_from_path(s) {
+            Some(0) => Self(Stream::stdin()),
+            Some(fd) => Self(Stream::fd(fd)),
+            None => Self(Stream::file(s)),
+        }
+    }
 }
 
-impl Default for Output {
+impl Default for Input {
     fn default() -> Self {
-        Self(Stream::stdout())
+        Self(Stream::stdin())
     }
 }
 
-impl fmt::Display for Output {
+impl fmt::Display for Input {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         self.0.fmt(f)
     }
 }
 
-impl FromStr for Output {
+impl FromStr for Input {
     type Err = std::convert::Infallible;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(Self::from(s.as_ref()))
+        Ok(Self::from(OsStr::new(s)))
     }
 }
 
-impl From<&OsStr> for Output {
+/// Parse `-` and `<stdin>` as stdin and anything else as a file path.
+///
+/// With the `http` feature, an `http://` or `https://` URL is parsed as a URL whose response
+/// body is read when the input is opened.
+impl From<&OsStr> for Input {
     fn from(s: &OsStr) -> Self {
-        if s == STDIO || s == STDOUT {
-            Self(Stream::stdout())
-        } else {
-            Self(Stream::file(s))
-        }
+        Self::parse(s, Some(STDIO.as_ref()))
     }
 }
 
-impl From<Output> for OsString {
-    fn from(output: Output) -> Self {
-        output.0.into()
+/// Always a file, like [`Input::from_path`].
+impl From<PathBuf> for Input {
+    fn from(path: PathBuf) -> Self {
+        Self::from_path(path)
     }
 }
 
-#[derive(Debug, Clone)]
-enum Stream {
-    File(PathBuf),
-    Stdin { tty: bool },
-    Stdout { tty: bool },
+/// Always a file, like [`Input::from_path`].
+impl From<&Path> for Input {
+    fn from(path: &Path) -> Self {
+        Self::from_path(path)
+    }
+}
+
+/// Read from an already open file, like [`Input::from_reader`].
+impl From<File> for Input {
+    fn from(file: File) -> Self {
+        Self::from_reader(file)
+    }
+}
+
+/// Read from stdin, like [`Input::stdin`].
+impl From<io::Stdin> for Input {
+    fn from(_: io::Stdin) -> Self {
+        Self::stdin()
+    }
+}
+
+/// Converts to an argument that parses back to the same input.
+///
+/// Stdin is always written as `<stdin>`, whether it was parsed from `-` or not. A file path
+/// that would parse as something else is escaped with a `.` component, so a file named `-`
+/// is written as `./-` and `/dev/stdin` as `/dev/./stdin`. These parse back to a file that
+/// compares equal to the original. Inputs from [`Input::from_reader`] can't be passed as an
+/// argument and are written as `<reader>`.
+///
+/// ```
+/// use std::ffi::{OsStr, OsString};
+///
+/// use clap_io::Input;
+///
+/// let inputs = [
+///     Input::from(OsStr::new("-")),
+///     Input::stdin(),
+///     Input::from_path("input.txt"),
+///     Input::from_path("-"),
+///     Input::from_path("<stdin>"),
+/// ];
+/// for input in inputs {
+///     let arg = OsString::from(input.clone());
+///     assert_eq!(Input::from(arg.as_os_str()), input);
+/// }
+/// ```
+impl From<Input> for OsString {
+    fn from(input: Input) -> Self {
+        match input.0 {
+            Stream::File(path) => escape_path(path, |s| Input::from(s).is_file()),
+            stream => stream.into(),
+        }
+    }
+}
+
+/// Either a file or stdout.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Output(Stream);
+
+impl Output {
+    /// An output that writes to the file at `path`.
+    ///
+    /// Unlike parsing, `-` and `<stdout>` are not treated specially.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        Self(Stream::File(path.into()))
+    }
+
+    /// An output in `dir` named after the input file, for converting many inputs at once.
+    ///
+    /// The extension of the file name is replaced with `extension` if given, or removed if it
+    /// is empty. Fails with [`io::ErrorKind::InvalidInput`] if the input isn't a file path or
+    /// if the output would be the input file itself.
+    ///
+    /// Inputs with the same file name in different directories get the same output, so use
+    /// [`Output::in_dir_all`] to name the outputs of many inputs and detect collisions.
+    pub fn in_dir(
+        input: &Input,
+        dir: impl AsRef<Path>,
+        extension: Option<&str>,
+    ) -> io::Result<Self> {
+        let invalid = |msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Failed to name an output for `{}`. Cause: {}", input, msg),
+            )
+        };
+        let name = input
+            .path()
+            .and_then(Path::file_name)
+            .ok_or_else(|| invalid("the input is not a file"))?;
+        let mut path = dir.as_ref().join(name);
+        if let Some(extension) = extension {
+            path.set_extension(extension);
+        }
+        if same_file(input.path().unwrap(), &path) {
+            return Err(invalid("the output would overwrite the input"));
+        }
+        Ok(Self::from_path(path))
+    }
+
+    /// Outputs in `dir` for each of `inputs`, named as with [`Output::in_dir`].
+    ///
+    /// Fails with [`io::ErrorKind::InvalidInput`] if two inputs would be written to the same
+    /// output, as for `a/x.csv` and `b/x.csv`, instead of letting one overwrite the other.
+    pub fn in_dir_all(
+        inputs: &[Input],
+        dir: impl AsRef<Path>,
+        extension: Option<&str>,
+    ) -> io::Result<Vec<Self>> {
+        let mut seen = HashMap::new();
+        let mut outputs = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            let output = Self::in_dir(input, dir.as_ref(), extension)?;
+            if let Some(other) = seen.insert(output.expect_path().to_owned(), input) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Failed to name an output for `{}`. Cause: `{}` would also be written to `{}`",
+                        input, other, output
+                    ),
+                ));
+            }
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+
+    /// An output that writes to stdout.
+    pub fn stdout() -> Self {
+        Self(Stream::stdout())
+    }
+
+    /// An output that writes to stderr.
+    pub fn stderr() -> Self {
+        Self(Stream::stderr())
+    }
+
+    /// An output that writes to the inherited file descriptor `fd`.
+    ///
+    /// See [`Input::from_fd`] for the ownership of the descriptor. Descriptors 1 and 2 are
+    /// stdout and stderr.
+    #[cfg(unix)]
+    pub fn from_fd(fd: std::os::fd::RawFd) -> Self {
+        match fd {
+            1 => Self::stdout(),
+            2 => Self::stderr(),
+            fd => Self(Stream::fd(fd)),
+        }
+    }
+
+    /// An output that writes to `writer`.
+    ///
+    /// The writer can only be opened once, including through clones of the output.
+    pub fn to_writer(writer: impl Write + Send + 'static) -> Self {
+        Self(Stream::Writer(Handle::new(Box::new(writer))))
+    }
+
+    /// An output that discards everything written to it, parsed from `<null>` ([`NULL`]).
+    ///
+    /// Opening it doesn't touch the disk, so combined with [`Output::open_counting`] it
+    /// measures how much a command would write without writing it.
+    pub fn sink() -> Self {
+        Self(Stream::Null)
+    }
+
+    /// An output that captures the written bytes in memory, for example to test a command
+    /// without touching the disk or stdout.
+    ///
+    /// The returned [`MemoryHandle`] gives the bytes written once the writer is dropped.
+    pub fn in_memory() -> (Self, MemoryHandle) {
+        let handle = MemoryHandle::default();
+        (Self::to_writer(handle.writer()), handle)
+    }
+
+    /// Open the output stream.
+    pub fn open(self) -> Result<Box<dyn Write + 'static>, OpenError> {
+        self.open_ref()
+    }
+
+    /// Open the output stream without consuming the output.
+    ///
+    /// This can be called again to reopen the output, for example to retry after a transient
+    /// error. Each call truncates a file, but reopening stdout appends to everything already
+    /// written to it. An output from [`Output::to_writer`] can only be opened once.
+    pub fn open_ref(&self) -> Result<Box<dyn Write + 'static>, OpenError> {
+        self.open_with(OpenOptions::new().write(true).create(true).truncate(true))
+    }
+
+    /// Open the output stream, first expanding a leading `~` and any `$VAR` or `${VAR}` in a
+    /// file path.
+    ///
+    /// See [`Input::open_expanded`].
+    pub fn open_expanded(self) -> Result<Box<dyn Write + 'static>, OpenError> {
+        match &self.0 {
+            Stream::File(path) => {
+                let path = expand::expand(path).map_err(|e| OpenError::output(&self.0, e))?;
+                Output(Stream::File(path.into_owned())).open()
+            }
+            _ => self.open(),
+        }
+    }
+
+    /// Open the output stream, retrying transient errors as configured by `policy`.
+    pub fn open_with_retry(
+        self,
+        policy: RetryPolicy,
+    ) -> Result<Box<dyn Write + 'static>, OpenError> {
+        policy.retry(|| self.open_ref())
+    }
+
+    /// Open the output stream with a buffered writer.
+    ///
+    /// The buffer is flushed when the writer is dropped, but any error while
+    /// flushing is ignored. Call [`BufferedOutput::finish`] to observe it.
+    pub fn open_buffered(self) -> io::Result<BufferedOutput> {
+        let output = self.open()?;
+        Ok(BufferedOutput::new(BufWriter::new(output)))
+    }
+
+    /// Open the output stream with a buffer suited to where the output goes.
+    ///
+    /// A TTY gets a [`LineWriter`](io::LineWriter), which flushes after each newline so
+    /// output appears promptly. Files and pipes get a 64 KiB buffer for throughput, flushed
+    /// only when full. Either way the buffer is flushed when the writer is dropped, ignoring
+    /// any error, so call [`BufferedOutput::finish`] to observe it.
+    pub fn open_smart_buffered(self) -> io::Result<BufferedOutput> {
+        if self.is_tty() {
+            Ok(BufferedOutput::line(io::LineWriter::new(self.open()?)))
+        } else {
+            self.open_buffered_with_capacity(64 * 1024)
+        }
+    }
+
+    /// Open the output stream with a buffered writer of the given capacity.
+    ///
+    /// See [`Output::open_buffered`] for the flushing behavior.
+    pub fn open_buffered_with_capacity(self, capacity: usize) -> io::Result<BufferedOutput> {
+        let output = self.open()?;
+        Ok(BufferedOutput::new(BufWriter::with_capacity(
+            capacity, output,
+        )))
+    }
+
+    /// Open the output stream, syncing a file to disk when finished.
+    ///
+    /// Call [`SyncedOutput::finish`] before reporting success to observe errors from the sync.
+    /// Outputs that aren't files are only flushed.
+    pub fn open_synced(self) -> io::Result<SyncedOutput> {
+        Ok(SyncedOutput::new(self.open_enum()?))
+    }
+
+    /// Open the output stream as a tokio `AsyncWrite`.
+    ///
+    /// Only files, file descriptors, stdout, stderr and [`Output::sink`] can be opened, since
+    /// tokio has no way to write other streams without blocking. Unlike
+    /// `Output::open_async_futures`, which writes them on a background thread pool, the
+    /// clipboard or an output made with [`Output::to_writer`] fails with
+    /// [`io::ErrorKind::Unsupported`].
+    #[cfg(feature = "tokio")]
+    pub fn open_async(
+        self,
+    ) -> io::Result<std::pin::Pin<Box<dyn ::tokio::io::AsyncWrite + Send + 'static>>> {
+        match &self.0 {
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file().unwrap()?;
+                Ok(Box::pin(::tokio::fs::File::from_std(file)))
+            }
+            Stream::Stdout { .. } => Ok(Box::pin(::tokio::io::stdout())),
+            Stream::Stderr { .. } => Ok(Box::pin(::tokio::io::stderr())),
+            Stream::Null => Ok(Box::pin(::tokio::io::sink())),
+            Stream::Writer(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as an async output", self.0),
+            )),
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as an async output", self.0),
+            )),
+            #[cfg(feature = "http")]
+            Stream::Url(_) => unreachable!("a URL is an input"),
+            Stream::Stdin { .. } | Stream::Reader(_) => unreachable!("stdin is an input"),
+        }
+    }
+
+    /// Open the output stream as a `futures` `AsyncWrite`.
+    ///
+    /// Writes are performed on a background thread pool.
+    #[cfg(feature = "futures")]
+    pub fn open_async_futures(
+        self,
+    ) -> io::Result<std::pin::Pin<Box<dyn futures_io::AsyncWrite + Send + 'static>>> {
+        match &self.0 {
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file().unwrap()?;
+                Ok(Box::pin(blocking::Unblock::new(file)))
+            }
+            Stream::Stdout { .. } => Ok(Box::pin(blocking::Unblock::new(io::stdout()))),
+            Stream::Stderr { .. } => Ok(Box::pin(blocking::Unblock::new(io::stderr()))),
+            Stream::Null => Ok(Box::pin(blocking::Unblock::new(io::sink()))),
+            Stream::Writer(handle) => Ok(Box::pin(blocking::Unblock::new(handle.take()?))),
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => Ok(Box::pin(blocking::Unblock::new(
+                clipboard::ClipboardWriter::new(),
+            ))),
+            #[cfg(feature = "http")]
+            Stream::Url(_) => unreachable!("a URL is an input"),
+            Stream::Stdin { .. } | Stream::Reader(_) => unreachable!("stdin is an input"),
+        }
+    }
+
+    /// Open the output stream without holding the stdout or stderr lock.
+    ///
+    /// Each write to a standard stream takes and releases the lock, which is slower than the
+    /// locked writer from [`Output::open`] but lets other code in the process, such as logging,
+    /// write in between. Other outputs open as usual.
+    pub fn open_unlocked(self) -> Result<Box<dyn Write + 'static>, OpenError> {
+        let writer: Box<dyn Write + 'static> = match self.0 {
+            Stream::Stdout { .. } => Box::new(io::stdout()),
+            Stream::Stderr { .. } => Box::new(io::stderr()),
+            _ => return self.open(),
+        };
+        #[cfg(feature = "tracing")]
+        let writer = Box::new(trace::Traced::new(writer, self.0.opened("output")));
+        Ok(writer)
+    }
+
+    /// Open the output stream as a writer that can be sent to another thread.
+    ///
+    /// The stdout and stderr locks can't be sent between threads, so they are opened unlocked
+    /// as with [`Output::open_unlocked`].
+    pub fn open_send(self) -> Result<Box<dyn Write + Send + 'static>, OpenError> {
+        let writer: Box<dyn Write + Send + 'static> = match self.0 {
+            Stream::Stdout { .. } => Box::new(io::stdout()),
+            Stream::Stderr { .. } => Box::new(io::stderr()),
+            _ => match self.open_enum_ref()? {
+                OutputWriter::File(file) => Box::new(file),
+                OutputWriter::Writer(writer) => writer,
+                OutputWriter::Stdout(_) | OutputWriter::Stderr(_) => {
+                    unreachable!("standard streams are opened unlocked")
+                }
+            },
+        };
+        #[cfg(feature = "tracing")]
+        let writer = Box::new(trace::Traced::new(writer, self.0.opened("output")));
+        Ok(writer)
+    }
+
+    /// Open the output stream, holding an exclusive advisory lock on a file until the writer is
+    /// dropped.
+    ///
+    /// This blocks while another process holds a lock on the file, which is only truncated once
+    /// the lock is acquired. Other outputs open as usual.
+    #[cfg(feature = "locking")]
+    pub fn open_locked(self) -> io::Result<Box<dyn Write + 'static>> {
+        match self.open_file_with(OpenOptions::new().write(true).create(true)) {
+            Some(file) => {
+                let file = file?;
+                fs2::FileExt::lock_exclusive(&file).map_err(|e| output_error(&self.0, e))?;
+                if self.is_file() {
+                    file.set_len(0).map_err(|e| output_error(&self.0, e))?;
+                }
+                let writer = Box::new(file);
+                #[cfg(feature = "tracing")]
+                let writer = Box::new(trace::Traced::new(writer, self.0.opened("output")));
+                Ok(writer)
+            }
+            None => Ok(self.open()?),
+        }
+    }
+
+    /// Open the output as stdout.
+    pub fn open_stdout(self) -> Result<io::StdoutLock<'static>, Self> {
+        match self.0 {
+            Stream::Stdout { .. } => Ok(io::stdout().lock()),
+            _ => Err(self),
+        }
+    }
+
+    /// Open the output as stderr.
+    pub fn open_stderr(self) -> Result<io::StderrLock<'static>, Self> {
+        match self.0 {
+            Stream::Stderr { .. } => Ok(io::stderr().lock()),
+            _ => Err(self),
+        }
+    }
+
+    /// Open the output stream, compressing it based on the file extension.
+    ///
+    /// Files ending in `.gz`, `.zst`, `.bz2` or `.xz` are compressed with the format's
+    /// default level. Any other output, including stdout, is written as is. Call
+    /// [`CompressedOutput::finish`] to finish the compressed stream and observe any error.
+    #[cfg(feature = "compression")]
+    pub fn open_compressed(self) -> io::Result<CompressedOutput> {
+        self.open_compressed_inner(None)
+    }
+
+    /// Open the output stream, compressing it at `level` based on the file extension.
+    ///
+    /// See [`Output::open_compressed`] for the supported extensions. Fails with
+    /// [`io::ErrorKind::InvalidInput`] before opening the output if `level` is out of range
+    /// for the format, which is 1 to 9 for bzip2, 0 to 9 for gzip and xz, and the levels
+    /// supported by the zstd library.
+    #[cfg(feature = "compression")]
+    pub fn open_compressed_with_level(self, level: u32) -> io::Result<CompressedOutput> {
+        self.open_compressed_inner(Some(level))
+    }
+
+    /// Open the output stream, compressing it with `compression` regardless of the path.
+    ///
+    /// This also compresses stdout. Pass `None` to write the output as is.
+    #[cfg(feature = "compression")]
+    pub fn open_compressed_as(
+        self,
+        compression: Option<Compression>,
+    ) -> io::Result<CompressedOutput> {
+        let encoder = compression.map(|c| c.encoder(None)).transpose()?;
+        let output = self.open()?;
+        match encoder {
+            Some(encoder) => encoder.wrap(output),
+            None => Ok(CompressedOutput::plain(output)),
+        }
+    }
+
+    #[cfg(feature = "compression")]
+    fn open_compressed_inner(self, level: Option<u32>) -> io::Result<CompressedOutput> {
+        let compression = self.path().and_then(Compression::from_path);
+        let encoder = compression.map(|c| c.encoder(level)).transpose()?;
+        let output = self.open()?;
+        match encoder {
+            Some(encoder) => encoder.wrap(output),
+            None => Ok(CompressedOutput::plain(output)),
+        }
+    }
+
+    /// Open the output stream, converting every line ending written to `ending`.
+    ///
+    /// See [`Input::open_normalized`] for the conversion. A trailing `\r` is held back until
+    /// the next write shows whether it is part of a `\r\n`, or until the writer is dropped.
+    pub fn open_normalized(self, ending: LineEnding) -> io::Result<Box<dyn Write + 'static>> {
+        Ok(Box::new(line_ending::NormalizeWriter::new(
+            self.open()?,
+            ending,
+        )))
+    }
+
+    /// Open the output stream, counting the bytes written.
+    pub fn open_counting(self) -> io::Result<(Box<dyn Write + 'static>, Arc<AtomicU64>)> {
+        let (writer, count) = count::Counting::new(self.open()?);
+        Ok((Box::new(writer), count))
+    }
+
+    /// Open this output and `also`, copying every write to both like `tee`.
+    ///
+    /// Writes stop at the first error. Every flush reaches both outputs and reports the
+    /// first error, and both outputs are flushed when the writer is dropped.
+    pub fn open_tee(self, also: Output) -> io::Result<Box<dyn Write + 'static>> {
+        let writers = vec![self.open()?, also.open()?];
+        Ok(Box::new(tee::Tee::new(writers)))
+    }
+
+    /// Open the output stream, writing at most `bytes_per_sec` bytes per second.
+    ///
+    /// Writes sleep as needed to stay under the rate, which is averaged over one second.
+    pub fn open_throttled(self, bytes_per_sec: u64) -> io::Result<Box<dyn Write + 'static>> {
+        let rate = throttle::rate(bytes_per_sec)?;
+        Ok(Box::new(throttle::Throttled::new(self.open()?, rate)))
+    }
+
+    /// Open the output stream, setting the Unix permission bits of the file to `mode`.
+    ///
+    /// The mode is ignored on other platforms and for outputs that aren't files.
+    pub fn open_with_mode(self, mode: u32) -> io::Result<Box<dyn Write + 'static>> {
+        match &self.0 {
+            Stream::File(_) => {
+                let file = self.open_file().unwrap()?;
+                set_file_mode(&file, mode).map_err(|e| output_error(&self.0, e))?;
+                let writer = Box::new(file);
+                #[cfg(feature = "tracing")]
+                let writer = Box::new(trace::Traced::new(writer, self.0.opened("output")));
+                Ok(writer)
+            }
+            _ => Ok(self.open()?),
+        }
+    }
+
+    /// Open the output stream, first renaming an existing file to its path with `suffix`.
+    ///
+    /// For example with a suffix of `.bak`, an existing `out.txt` is kept as `out.txt.bak`.
+    pub fn open_with_backup(self, suffix: &str) -> io::Result<Box<dyn Write + 'static>> {
+        if let Stream::File(path) = &self.0 {
+            let mut backup = path.clone().into_os_string();
+            backup.push(suffix);
+            match fs::rename(path, &backup) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    return Err(output_error(&self.0, e));
+                }
+                _ => {}
+            }
+        }
+        Ok(self.open()?)
+    }
+
+    /// Open the output stream, treating a closed pipe as the end of the output.
+    ///
+    /// When stdout is piped to a command like `head` that exits early, writes fail with
+    /// [`io::ErrorKind::BrokenPipe`] on both Unix, where Rust ignores `SIGPIPE`, and Windows.
+    /// The returned writer swallows that error and discards later writes, so a program can
+    /// finish quietly or stop once [`PipeOutput::is_closed`] returns true. On Unix,
+    /// [`reset_sigpipe`] instead makes the process exit when the pipe closes.
+    pub fn open_ignore_broken_pipe(self) -> Result<PipeOutput, OpenError> {
+        Ok(PipeOutput::new(self.open()?))
+    }
+
+    /// Open the output stream for binary data, failing if it is a TTY.
+    ///
+    /// This avoids garbling the terminal. The error has kind [`io::ErrorKind::InvalidInput`],
+    /// and any other output is opened as usual.
+    pub fn open_binary_safe(self) -> io::Result<Box<dyn Write + 'static>> {
+        if self.is_tty() {
+            let e = io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "refusing to write binary data to a terminal",
+            );
+            return Err(OpenError::output(&self.0, e).into());
+        }
+        Ok(self.open()?)
+    }
+
+    /// Open the output stream, appending to the file if it already exists.
+    pub fn open_append(self) -> io::Result<Box<dyn Write + 'static>> {
+        Ok(self.open_with(OpenOptions::new().append(true).create(true))?)
+    }
+
+    /// Open the output stream, failing with [`io::ErrorKind::AlreadyExists`] if the file exists.
+    pub fn open_no_clobber(self) -> io::Result<Box<dyn Write + 'static>> {
+        Ok(self.open_with(OpenOptions::new().write(true).create_new(true))?)
+    }
+
+    /// Open the output stream, opening the file with `options`.
+    ///
+    /// This controls truncation, creation and appending in one place, for example
+    /// `OpenOptions::new().write(true).create(true)` overwrites the file in place without
+    /// truncating it. The options are ignored for outputs that aren't file paths.
+    pub fn open_with_options(self, options: OpenOptions) -> io::Result<Box<dyn Write + 'static>> {
+        Ok(self.open_with(&options)?)
+    }
+
+    /// Open the output stream, creating any missing parent directories of the file.
+    pub fn open_create_dirs(self) -> io::Result<Box<dyn Write + 'static>> {
+        self.create_parent_dirs()?;
+        Ok(self.open()?)
+    }
+
+    fn create_parent_dirs(&self) -> io::Result<()> {
+        if let Stream::File(path) = &self.0 {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent).map_err(|e| output_error(&self.0, e))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Open the output as a file.
+    pub fn open_file(&self) -> Option<Result<File, OpenError>> {
+        self.open_file_with(OpenOptions::new().write(true).create(true).truncate(true))
+    }
+
+    /// Open the output stream as a concrete [`OutputWriter`] instead of a boxed writer.
+    ///
+    /// Matching on the writer gives access to the underlying [`File`] or standard stream lock.
+    pub fn open_enum(self) -> io::Result<OutputWriter> {
+        Ok(self.open_enum_ref()?)
+    }
+
+    /// Open the output as a file, for example to lock it.
+    ///
+    /// Unlike [`Output::open_file`], fails with [`io::ErrorKind::Unsupported`] if the output is
+    /// not a file.
+    pub fn open_file_handle(self) -> io::Result<File> {
+        match self.open_file() {
+            Some(file) => Ok(file?),
+            None => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Failed to open {} as a file", self.0),
+            )),
+        }
+    }
+
+    fn open_with(&self, options: &OpenOptions) -> Result<Box<dyn Write + 'static>, OpenError> {
+        let writer: Box<dyn Write + 'static> = match &self.0 {
+            Stream::Writer(handle) => handle.take().map_err(|e| OpenError::output(&self.0, e))?,
+            _ => Box::new(self.open_enum_with(options)?),
+        };
+        #[cfg(feature = "tracing")]
+        let writer = Box::new(trace::Traced::new(writer, self.0.opened("output")));
+        Ok(writer)
+    }
+
+    fn open_enum_ref(&self) -> Result<OutputWriter, OpenError> {
+        self.open_enum_with(OpenOptions::new().write(true).create(true).truncate(true))
+    }
+
+    fn open_enum_with(&self, options: &OpenOptions) -> Result<OutputWriter, OpenError> {
+        match &self.0 {
+            Stream::File(_) | Stream::Fd { .. } => {
+                let file = self.open_file_with(options).unwrap()?;
+                Ok(OutputWriter::File(file))
+            }
+            Stream::Stdout { .. } => Ok(OutputWriter::Stdout(io::stdout().lock())),
+            Stream::Stderr { .. } => Ok(OutputWriter::Stderr(io::stderr().lock())),
+            Stream::Writer(handle) => {
+                let writer = handle.take().map_err(|e| OpenError::output(&self.0, e))?;
+                Ok(OutputWriter::Writer(writer))
+            }
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => Ok(OutputWriter::Writer(Box::new(
+                clipboard::ClipboardWriter::new(),
+            ))),
+            Stream::Null => Ok(OutputWriter::Writer(Box::new(io::sink()))),
+            #[cfg(feature = "http")]
+            Stream::Url(_) => unreachable!("a URL is an input"),
+            Stream::Stdin { .. } | Stream::Reader(_) => unreachable!("stdin is an input"),
+        }
+    }
+
+    fn open_file_with(&self, options: &OpenOptions) -> Option<Result<File, OpenError>> {
+        let file = match &self.0 {
+            Stream::File(path) => options.open(path),
+            Stream::Fd { fd, .. } => open_fd(*fd),
+            _ => return None,
+        };
+        Some(file.map_err(|e| OpenError::output(&self.0, e)))
+    }
+
+    /// Get the name of the output: the path of a file, or `<stdout>`.
+    pub fn name(&self) -> Cow<'_, str> {
+        self.0.name()
+    }
+
+    /// Display the output, showing stdout as `stdout` instead of `<stdout>`.
+    pub fn display_as<'a>(&'a self, stdout: &'a str) -> DisplayAs<'a> {
+        DisplayAs {
+            stream: &self.0,
+            name: matches!(self.0, Stream::Stdout { .. }).then_some(stdout),
+        }
+    }
+
+    /// Get the kind of stream this output refers to.
+    pub fn kind(&self) -> StreamKind {
+        self.0.kind()
+    }
+
+    /// Is this output stdout?
+    pub fn is_stdout(&self) -> bool {
+        matches!(self.0, Stream::Stdout { .. })
+    }
+
+    /// Is this output stderr?
+    pub fn is_stderr(&self) -> bool {
+        matches!(self.0, Stream::Stderr { .. })
+    }
+
+    /// Is this output a file path?
+    pub fn is_file(&self) -> bool {
+        matches!(self.0, Stream::File(_))
+    }
+
+    /// Is this output a TTY?
+    ///
+    /// The terminal status is queried each time this is called.
+    pub fn is_tty(&self) -> bool {
+        self.0.is_tty()
+    }
+
+    /// Should colored text be written to this output?
+    ///
+    /// A non-empty `NO_COLOR` disables color and a `CLICOLOR_FORCE` other than `0` forces it,
+    /// checked in that order. Otherwise color is used for a TTY unless `CLICOLOR` is `0`.
+    pub fn should_colorize(&self) -> bool {
+        let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+        if var("NO_COLOR").is_some() {
+            return false;
+        }
+        if var("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+            return true;
+        }
+        var("CLICOLOR").map_or(true, |v| v != "0") && self.is_tty()
+    }
+
+    /// Was this output a TTY when it was parsed?
+    pub fn was_tty_at_parse(&self) -> bool {
+        self.0.was_tty()
+    }
+
+    /// If the output is a file get the path.
+    pub fn path(&self) -> Option<&Path> {
+        self.0.path()
+    }
+
+    /// Get the path of the output file, failing if the output isn't a file.
+    pub fn try_path(&self) -> Result<&Path, NotAFileError> {
+        self.0.path().ok_or_else(|| NotAFileError::new(&self.0))
+    }
+
+    /// Get the path of the output file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the output isn't a file.
+    #[track_caller]
+    pub fn expect_path(&self) -> &Path {
+        match self.try_path() {
+            Ok(path) => path,
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    /// Check that the output can be opened for writing, without truncating or creating it.
+    ///
+    /// An existing file is opened for writing without truncation and closed again. A named pipe
+    /// is opened without waiting for a reader, and passes if nothing is reading from it yet. For
+    /// a new file, the parent directory must exist and be writable. Other outputs, such as
+    /// stdout, are only checked when they are opened.
+    pub fn check(&self) -> io::Result<()> {
+        let path = match &self.0 {
+            Stream::File(path) => path,
+            Stream::Fd { .. } => return self.open_file().unwrap().map(drop).map_err(Into::into),
+            _ => return Ok(()),
+        };
+        let result = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_dir() => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Is a directory",
+            )),
+            Ok(_) => match nonblocking(OpenOptions::new().write(true)).open(path) {
+                // A named pipe with no reader yet
+                #[cfg(unix)]
+                Err(e) if e.raw_os_error() == Some(libc::ENXIO) => Ok(()),
+                result => result.map(drop),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+                check_dir_writable(parent.unwrap_or(Path::new(".")))
+            }
+            Err(e) => Err(e),
+        };
+        result.map_err(|e| output_error(&self.0, e))
+    }
+
+    fn parse(s: &OsStr, stdio: Option<&OsStr>) -> Self {
+        if s == STDOUT || Some(s) == stdio {
+            return Self(Stream::stdout());
+        }
+        if s == STDERR {
+            return Self(Stream::stderr());
+        }
+        if s == NULL {
+            return Self(Stream::Null);
+        }
+        #[cfg(feature = "clipboard")]
+        if s == CLIPBOARD {
+            return Self(Stream::Clipboard);
+        }
+        match fd_from_path(s) {
+            Some(1) => Self(Stream::stdout()),
+            Some(2) => Self(Stream::stderr()),
+            Some(fd) => Self(Stream::fd(fd)),
+            None => Self(Stream::file(s)),
+        }
+    }
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Self(Stream::stdout())
+    }
+}
+
+impl fmt::Display for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for Output {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from(OsStr::new(s)))
+    }
+}
+
+/// Parse `-` and `<stdout>` as stdout, `<stderr>` as stderr, `<null>` as [`Output::sink`] and
+/// anything else as a file path.
+impl From<&OsStr> for Output {
+    fn from(s: &OsStr) -> Self {
+        Self::parse(s, Some(STDIO.as_ref()))
+    }
+}
+
+/// Always a file, like [`Output::from_path`].
+impl From<PathBuf> for Output {
+    fn from(path: PathBuf) -> Self {
+        Self::from_path(path)
+    }
+}
+
+/// Always a file, like [`Output::from_path`].
+impl From<&Path> for Output {
+    fn from(path: &Path) -> Self {
+        Self::from_path(path)
+    }
+}
+
+/// Converts to an argument that parses back to the same output.
+///
+/// Stdout is always written as `<stdout>`, and a file path that would parse as something else
+/// is escaped with a `.` component, as for [`Input`].
+impl From<Output> for OsString {
+    fn from(output: Output) -> Self {
+        match output.0 {
+            Stream::File(path) => escape_path(path, |s| Output::from(s).is_file()),
+            stream => stream.into(),
+        }
+    }
+}
+
+/// Displays an [`Input`] or [`Output`] with a custom name for its standard stream.
+///
+/// Created by [`Input::display_as`] and [`Output::display_as`].
+#[derive(Debug, Clone, Copy)]
+pub struct DisplayAs<'a> {
+    stream: &'a Stream,
+    name: Option<&'a str>,
+}
+
+impl fmt::Display for DisplayAs<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.name {
+            Some(name) => name.fmt(f),
+            None => self.stream.fmt(f),
+        }
+    }
+}
+
+/// A reader that can also seek.
+pub trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek + ?Sized> ReadSeek for T {}
+
+#[derive(Debug, Clone)]
+enum Stream {
+    File(PathBuf),
+    Stdin {
+        tty: bool,
+    },
+    Stdout {
+        tty: bool,
+    },
+    Stderr {
+        tty: bool,
+    },
+    Fd {
+        fd: i32,
+        tty: bool,
+    },
+    #[cfg(feature = "http")]
+    Url(String),
+    #[cfg(feature = "clipboard")]
+    Clipboard,
+    Null,
+    Reader(Handle<dyn Read + Send>),
+    Writer(Handle<dyn Write + Send>),
 }
 
 impl Stream {
@@ -298,18 +2013,95 @@ impl Stream {
 
     fn stdin() -> Self {
         Self::Stdin {
-            tty: atty::is(atty::Stream::Stdin),
+            tty: tty_override().unwrap_or_else(|| io::stdin().is_terminal()),
         }
     }
 
     fn stdout() -> Self {
         Self::Stdout {
-            tty: atty::is(atty::Stream::Stdout),
+            tty: tty_override().unwrap_or_else(|| io::stdout().is_terminal()),
+        }
+    }
+
+    fn stderr() -> Self {
+        Self::Stderr {
+            tty: tty_override().unwrap_or_else(|| io::stderr().is_terminal()),
+        }
+    }
+
+    fn fd(fd: i32) -> Self {
+        Self::Fd {
+            fd,
+            tty: tty_override().unwrap_or_else(|| fd_is_terminal(fd)),
         }
     }
 
     fn is_tty(&self) -> bool {
-        matches!(self, Self::Stdin { tty } | Self::Stdout { tty } if *tty)
+        match self {
+            Self::File(_) | Self::Null | Self::Reader(_) | Self::Writer(_) => false,
+            #[cfg(feature = "http")]
+            Self::Url(_) => false,
+            #[cfg(feature = "clipboard")]
+            Self::Clipboard => false,
+            Self::Stdin { .. } => tty_override().unwrap_or_else(|| io::stdin().is_terminal()),
+            Self::Stdout { .. } => tty_override().unwrap_or_else(|| io::stdout().is_terminal()),
+            Self::Stderr { .. } => tty_override().unwrap_or_else(|| io::stderr().is_terminal()),
+            Self::Fd { fd, .. } => tty_override().unwrap_or_else(|| fd_is_terminal(*fd)),
+        }
+    }
+
+    fn was_tty(&self) -> bool {
+        matches!(
+            self,
+            Self::Stdin { tty }
+                | Self::Stdout { tty }
+                | Self::Stderr { tty }
+                | Self::Fd { tty, .. } if *tty
+        )
+    }
+
+    fn name(&self) -> Cow<'_, str> {
+        match self {
+            Self::File(path) => path.to_string_lossy(),
+            Self::Stdin { .. } => STDIN.into(),
+            Self::Stdout { .. } => STDOUT.into(),
+            Self::Stderr { .. } => STDERR.into(),
+            Self::Fd { fd, .. } => format!("{}{}", FD_PREFIX, fd).into(),
+            #[cfg(feature = "http")]
+            Self::Url(url) => url.as_str().into(),
+            #[cfg(feature = "clipboard")]
+            Self::Clipboard => CLIPBOARD.into(),
+            Self::Null => NULL.into(),
+            Self::Reader(_) => READER.into(),
+            Self::Writer(_) => WRITER.into(),
+        }
+    }
+
+    /// Emit a debug event for opening the stream, and another when the result is dropped.
+    #[cfg(feature = "tracing")]
+    fn opened(&self, kind: &'static str) -> trace::Opened {
+        trace::Opened::new(kind, self.name().into_owned())
+    }
+
+    fn kind(&self) -> StreamKind {
+        match self {
+            Self::File(_) => StreamKind::File,
+            Self::Stdin { .. } => StreamKind::Stdin,
+            Self::Stdout { .. } => StreamKind::Stdout,
+            Self::Stderr { .. } => StreamKind::Stderr,
+            Self::Fd { .. } => StreamKind::Fd,
+            #[cfg(feature = "http")]
+            Self::Url(_) => StreamKind::Url,
+            #[cfg(feature = "clipboard")]
+            Self::Clipboard => StreamKind::Clipboard,
+            Self::Null => StreamKind::Null,
+            Self::Reader(_) => StreamKind::Reader,
+            Self::Writer(_) => StreamKind::Writer,
+        }
+    }
+
+    fn is_fd(&self) -> bool {
+        matches!(self, Self::Fd { .. })
     }
 
     fn path(&self) -> Option<&Path> {
@@ -321,22 +2113,556 @@ impl Stream {
     }
 }
 
-impl fmt::Display for Stream {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// Streams are equal if they refer to the same file or standard stream, regardless of
+/// whether it was a TTY.
+impl PartialEq for Stream {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::File(a), Self::File(b)) => without_cur_dir(a) == without_cur_dir(b),
+            (Self::Stdin { .. }, Self::Stdin { .. }) => true,
+            (Self::Stdout { .. }, Self::Stdout { .. }) => true,
+            (Self::Stderr { .. }, Self::Stderr { .. }) => true,
+            (Self::Fd { fd: a, .. }, Self::Fd { fd: b, .. }) => a == b,
+            #[cfg(feature = "http")]
+            (Self::Url(a), Self::Url(b)) => a == b,
+            #[cfg(feature = "clipboard")]
+            (Self::Clipboard, Self::Clipboard) => true,
+            (Self::Null, Self::Null) => true,
+            (Self::Reader(a), Self::Reader(b)) => a.ptr_eq(b),
+            (Self::Writer(a), Self::Writer(b)) => a.ptr_eq(b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Stream {}
+
+/// Hashes consistently with `PartialEq`, ignoring the TTY status.
+impl Hash for Stream {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
         match self {
-            Self::File(path) => path.display().fmt(f),
-            Self::Stdin { .. } => STDIN.fmt(f),
-            Self::Stdout { .. } => STDOUT.fmt(f),
+            Self::File(path) => without_cur_dir(path).hash(state),
+            Self::Fd { fd, .. } => fd.hash(state),
+            #[cfg(feature = "http")]
+            Self::Url(url) => url.hash(state),
+            #[cfg(feature = "clipboard")]
+            Self::Clipboard => {}
+            Self::Reader(handle) => handle.addr().hash(state),
+            Self::Writer(handle) => handle.addr().hash(state),
+            Self::Stdin { .. } | Self::Stdout { .. } | Self::Stderr { .. } | Self::Null => {}
         }
     }
 }
 
+impl fmt::Display for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.name().fmt(f)
+    }
+}
+
 impl From<Stream> for OsString {
     fn from(s: Stream) -> OsString {
         match s {
             Stream::File(path) => path.into(),
             Stream::Stdin { .. } => STDIN.into(),
             Stream::Stdout { .. } => STDOUT.into(),
+            Stream::Stderr { .. } => STDERR.into(),
+            Stream::Fd { fd, .. } => format!("{}{}", FD_PREFIX, fd).into(),
+            #[cfg(feature = "http")]
+            Stream::Url(url) => url.into(),
+            #[cfg(feature = "clipboard")]
+            Stream::Clipboard => CLIPBOARD.into(),
+            Stream::Null => NULL.into(),
+            Stream::Reader(_) => READER.into(),
+            Stream::Writer(_) => WRITER.into(),
+        }
+    }
+}
+
+/// Strip a leading `./`, which `Path` equality otherwise keeps, so `./-` and `-` are the same.
+fn without_cur_dir(path: &Path) -> &Path {
+    path.strip_prefix(".").unwrap_or(path)
+}
+
+/// Add a `.` component to `path` if it wouldn't parse back as a file.
+fn escape_path(path: PathBuf, is_file: impl Fn(&OsStr) -> bool) -> OsString {
+    if is_file(path.as_os_str()) {
+        return path.into();
+    }
+    if path.is_relative() {
+        return Path::new(".").join(path).into();
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent.join(".").join(name).into(),
+        _ => path.into(),
+    }
+}
+
+/// Is stderr a TTY?
+///
+/// Use this to decide how to format error messages, independently of where the output goes.
+pub fn stderr_is_tty() -> bool {
+    Output::stderr().is_tty()
+}
+
+/// Should colored error messages be written to stderr?
+///
+/// This follows the same conventions as [`Output::should_colorize`].
+pub fn stderr_should_colorize() -> bool {
+    Output::stderr().should_colorize()
+}
+
+/// Restore the default `SIGPIPE` behavior, so the process exits quietly when the reader of a
+/// pipe goes away, like traditional Unix tools.
+///
+/// Rust ignores `SIGPIPE`, so `tool | head` otherwise makes writes to stdout fail with
+/// [`io::ErrorKind::BrokenPipe`]. Call this at the start of `main`, before any output is
+/// written. It affects every pipe and socket in the process, so don't call it from a library
+/// or a program that writes to sockets. See [`Output::open_ignore_broken_pipe`] for a portable
+/// alternative.
+#[cfg(unix)]
+pub fn reset_sigpipe() {
+    // SAFETY: resetting SIGPIPE to its default disposition has no preconditions
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+    }
+}
+
+thread_local! {
+    static TTY_OVERRIDE: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Make the standard streams and file descriptors on the current thread report `tty` as their
+/// terminal status.
+///
+/// This is meant for testing TTY dependent behavior without a real terminal. It applies to
+/// `is_tty`, and to `was_tty_at_parse` for values parsed while it is set. Files and in-memory
+/// streams are never TTYs. Pass `None` to go back to querying the terminal.
+pub fn set_tty_override(tty: Option<bool>) {
+    TTY_OVERRIDE.with(|cell| cell.set(tty));
+}
+
+fn tty_override() -> Option<bool> {
+    TTY_OVERRIDE.with(Cell::get)
+}
+
+/// A caller supplied reader or writer, shared between clones and taken on open.
+struct Handle<T: ?Sized>(Arc<Mutex<Option<Box<T>>>>);
+
+impl<T: ?Sized> Handle<T> {
+    fn new(inner: Box<T>) -> Self {
+        Self(Arc::new(Mutex::new(Some(inner))))
+    }
+
+    fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    fn addr(&self) -> usize {
+        Arc::as_ptr(&self.0) as *const () as usize
+    }
+
+    fn take(&self) -> io::Result<Box<T>> {
+        let mut inner = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        inner
+            .take()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "Stream was already opened"))
+    }
+}
+
+impl<T: ?Sized> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Handle(..)")
+    }
+}
+
+/// Parse `/dev/stdin`, `/dev/stdout`, `/dev/stderr` and `/dev/fd/N` into a file descriptor.
+#[cfg(unix)]
+fn fd_from_path(s: &OsStr) -> Option<i32> {
+    let s = s.to_str()?;
+    match s {
+        "/dev/stdin" => Some(0),
+        "/dev/stdout" => Some(1),
+        "/dev/stderr" => Some(2),
+        _ => {
+            let fd = s.strip_prefix(FD_PREFIX)?;
+            // `parse` also accepts a leading `+`
+            if !fd.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            fd.parse::<u16>().ok().map(i32::from)
         }
     }
 }
+
+#[cfg(not(unix))]
+fn fd_from_path(_: &OsStr) -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn fd_is_terminal(fd: i32) -> bool {
+    // SAFETY: `isatty` accepts any descriptor number, failing with `EBADF` if it isn't open
+    unsafe { libc::isatty(fd) == 1 }
+}
+
+#[cfg(not(unix))]
+fn fd_is_terminal(_: i32) -> bool {
+    false
+}
+
+/// Open a duplicate of `fd`, leaving the original descriptor open.
+#[cfg(unix)]
+fn open_fd(fd: i32) -> io::Result<File> {
+    use std::os::fd::FromRawFd;
+    // SAFETY: `fcntl` accepts any descriptor number, failing with `EBADF` if it isn't open
+    let dup = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, 0) };
+    if dup == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: `dup` is a new descriptor owned by nothing else
+    Ok(unsafe { File::from_raw_fd(dup) })
+}
+
+#[cfg(not(unix))]
+fn open_fd(_: i32) -> io::Result<File> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "File descriptors are only supported on Unix",
+    ))
+}
+
+/// Read and discard the first `n` bytes of `input`.
+fn skip_bytes(input: &mut impl Read, n: u64) -> io::Result<()> {
+    io::copy(&mut input.take(n), &mut io::sink())?;
+    Ok(())
+}
+
+/// Copy `input` to `output` and flush it, returning the number of bytes copied.
+fn copy_opened(input: &mut InputReader, output: &mut OutputWriter) -> io::Result<u64> {
+    let n = match (&mut *input, &mut *output) {
+        // Passing the files themselves lets the standard library use `copy_file_range` on
+        // Linux. A 1 GiB copy measured no consistent difference from the buffered copy, so
+        // this is kept for the kernel side copy rather than for speed
+        (InputReader::File(input), OutputWriter::File(output)) => io::copy(input, output)?,
+        _ => io::copy(input, output)?,
+    };
+    output.flush()?;
+    Ok(n)
+}
+
+/// Replace the contents of `dst` with a copy-on-write clone of `src`.
+#[cfg(target_os = "linux")]
+fn reflink(src: &File, dst: &File) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: both descriptors are open for the duration of the call
+    let ret = unsafe { libc::ioctl(dst.as_raw_fd(), libc::FICLONE, src.as_raw_fd()) };
+    if ret == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink(_: &File, _: &File) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Cloning files is only supported on Linux",
+    ))
+}
+
+/// Discard bytes up to and including the next newline, returning how many were discarded.
+fn skip_line(reader: &mut dyn BufRead) -> io::Result<usize> {
+    let mut skipped = 0;
+    loop {
+        let buf = match reader.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if buf.is_empty() {
+            return Ok(skipped);
+        }
+        let (n, done) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (i + 1, true),
+            None => (buf.len(), false),
+        };
+        reader.consume(n);
+        skipped += n;
+        if done {
+            return Ok(skipped);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn file_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn file_mode(_: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+/// Don't block opening a named pipe until the other end is opened.
+fn nonblocking(options: &mut OpenOptions) -> &mut OpenOptions {
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(options, libc::O_NONBLOCK);
+    options
+}
+
+#[cfg(unix)]
+fn check_dir_writable(dir: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    let dir = std::ffi::CString::new(dir.as_os_str().as_bytes())?;
+    // SAFETY: `dir` is a valid NUL terminated string for the duration of the call
+    if unsafe { libc::access(dir.as_ptr(), libc::W_OK | libc::X_OK) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn check_dir_writable(dir: &Path) -> io::Result<()> {
+    if fs::metadata(dir)?.permissions().readonly() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "Directory is read only",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_file_mode(file: &File, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    file.set_permissions(fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_file_mode(_: &File, _: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Wrap `e` in an [`OpenError`] for `stream`, keeping `e` as the source.
+fn input_error(stream: &Stream, e: io::Error) -> io::Error {
+    OpenError::input(stream, e).into()
+}
+
+/// Wrap `e` in an [`OpenError`] for `stream`, keeping `e` as the source.
+fn output_error(stream: &Stream, e: io::Error) -> io::Error {
+    OpenError::output(stream, e).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+
+    #[test]
+    fn tty_override_only_applies_to_terminals() {
+        set_tty_override(Some(true));
+        assert!(Output::stdout().is_tty());
+        assert!(Input::stdin().is_tty());
+        assert!(!Output::from_path("out.txt").is_tty());
+        assert!(!Input::from_bytes("data").is_tty());
+        set_tty_override(None);
+    }
+
+    #[test]
+    fn sniff_leaves_non_files_unread() {
+        let input = Input::from_bytes(&b"\x1F\x8B"[..]);
+        assert_eq!(input.sniff().unwrap(), None);
+        let mut data = Vec::new();
+        input.open().unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"\x1F\x8B");
+    }
+
+    #[test]
+    fn stdin_can_be_reopened_after_drop() {
+        let input = Input::stdin();
+        let first = input.open_ref().unwrap();
+        assert!(input.open_ref().is_err());
+        drop(first);
+        assert!(input.open_ref().is_ok());
+    }
+
+    #[test]
+    fn open_range_of_reader() {
+        let input = Input::from_bytes(&b"0123456789"[..]);
+        let mut data = Vec::new();
+        input
+            .open_range(2, Some(3))
+            .unwrap()
+            .read_to_end(&mut data)
+            .unwrap();
+        assert_eq!(data, b"234");
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn invalid_compression_level_keeps_output() {
+        let path = std::env::temp_dir().join(format!("clap-io-{}.bz2", std::process::id()));
+        fs::write(&path, "keep").unwrap();
+        let e = Output::from_path(&path)
+            .open_compressed_with_level(0)
+            .err()
+            .unwrap();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "keep");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn in_dir_all_detects_collisions() {
+        let inputs = [Input::from_path("a/x.csv"), Input::from_path("a/y.csv")];
+        let outputs = Output::in_dir_all(&inputs, "out", Some("json")).unwrap();
+        assert_eq!(outputs[0], Output::from_path("out/x.json"));
+        assert_eq!(outputs[1], Output::from_path("out/y.json"));
+        let inputs = [Input::from_path("a/x.csv"), Input::from_path("b/x.csv")];
+        let e = Output::in_dir_all(&inputs, "out", Some("json")).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn input_os_string_round_trip() {
+        let mut inputs = vec![
+            Input::stdin(),
+            Input::from(OsStr::new("-")),
+            Input::from_path("in.txt"),
+            Input::from_path("./in.txt"),
+            Input::from_path("-"),
+            Input::from_path("<stdin>"),
+            Input::from_path("<clipboard>"),
+            Input::from_path("http://example.com"),
+        ];
+        #[cfg(unix)]
+        inputs.extend([
+            Input::from_path("/dev/stdin"),
+            Input::from_path("/dev/fd/3"),
+            Input::from_fd(0),
+            Input::from_fd(3),
+        ]);
+        for input in inputs {
+            let arg = OsString::from(input.clone());
+            assert_eq!(Input::from(arg.as_os_str()), input, "{:?}", arg);
+        }
+    }
+
+    #[test]
+    fn output_os_string_round_trip() {
+        let mut outputs = vec![
+            Output::stdout(),
+            Output::stderr(),
+            Output::from(OsStr::new("-")),
+            Output::from_path("out.txt"),
+            Output::from_path("-"),
+            Output::from_path("<stdout>"),
+            Output::from_path("<stderr>"),
+            Output::sink(),
+            Output::from_path("<null>"),
+        ];
+        #[cfg(unix)]
+        outputs.extend([
+            Output::from_path("/dev/stdout"),
+            Output::from_path("/dev/fd/3"),
+            Output::from_fd(1),
+            Output::from_fd(2),
+            Output::from_fd(3),
+        ]);
+        for output in outputs {
+            let arg = OsString::from(output.clone());
+            assert_eq!(Output::from(arg.as_os_str()), output, "{:?}", arg);
+        }
+    }
+
+    #[test]
+    fn sink_counts_discarded_bytes() {
+        let output = Output::from(OsStr::new("<null>"));
+        assert_eq!(output, Output::sink());
+        assert_eq!(output.kind(), StreamKind::Null);
+        let (mut writer, count) = output.open_counting().unwrap();
+        writer.write_all(b"discarded").unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 9);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn fd_paths_are_digits() {
+        assert_eq!(Input::from(OsStr::new("/dev/fd/3")), Input::from_fd(3));
+        for path in ["/dev/fd/+3", "/dev/fd/-3", "/dev/fd/", "/dev/fd/3x"] {
+            assert!(Input::from(OsStr::new(path)).is_file(), "{}", path);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn closed_fd_fails_to_open() {
+        let input = Input::from_fd(999);
+        assert!(!input.is_tty());
+        let e = input.open().err().unwrap();
+        assert_eq!(e.into_source().raw_os_error(), Some(libc::EBADF));
+    }
+
+    #[test]
+    fn skipped_lines_can_span_buffers() {
+        let mut reader = io::BufReader::with_capacity(2, &b"first line\nsecond\n\nlast"[..]);
+        assert_eq!(skip_line(&mut reader).unwrap(), 11);
+        assert_eq!(skip_line(&mut reader).unwrap(), 7);
+        assert_eq!(skip_line(&mut reader).unwrap(), 1);
+        assert_eq!(skip_line(&mut reader).unwrap(), 4);
+        assert_eq!(skip_line(&mut reader).unwrap(), 0);
+    }
+
+    #[test]
+    fn skipping_a_line_reads_no_further() {
+        let mut reader = io::BufReader::with_capacity(4, &b"ab\ncd\nef"[..]);
+        skip_line(&mut reader).unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "cd\nef");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checking_a_fifo_does_not_block() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = std::env::temp_dir().join(format!("clap-io-fifo-{}", std::process::id()));
+        let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        let input = Input::from_path(&path).check();
+        let output = Output::from_path(&path).check();
+        fs::remove_file(&path).unwrap();
+        input.unwrap();
+        output.unwrap();
+    }
+}
@@ -0,0 +1,93 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A reader that reaches EOF after a limited number of bytes.
+pub(crate) struct Limited<R> {
+    inner: R,
+    remaining: u64,
+    truncated: Arc<AtomicBool>,
+}
+
+impl<R> Limited<R> {
+    pub(crate) fn new(inner: R, limit: u64) -> (Self, Arc<AtomicBool>) {
+        let truncated = Arc::new(AtomicBool::new(false));
+        let limited = Self {
+            inner,
+            remaining: limit,
+            truncated: Arc::clone(&truncated),
+        };
+        (limited, truncated)
+    }
+}
+
+impl<R: Read> Read for Limited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            // Probe for one more byte to learn whether the input was cut short
+            if !self.truncated.load(Ordering::Relaxed) && self.inner.read(&mut [0])? > 0 {
+                self.truncated.store(true, Ordering::Relaxed);
+            }
+            return Ok(0);
+        }
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let n = self.inner.read(&mut buf[..max])?;
+        self.remaining -= n as u64;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read_all, OneByte};
+
+    #[test]
+    fn limit_at_eof_is_not_truncated() {
+        let (reader, truncated) = Limited::new(OneByte(&b"abc"[..]), 3);
+        assert_eq!(read_all(reader), b"abc");
+        assert!(!truncated.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn limit_before_eof_is_truncated() {
+        let (reader, truncated) = Limited::new(OneByte(&b"abcd"[..]), 3);
+        assert_eq!(read_all(reader), b"abc");
+        assert!(truncated.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn limit_past_eof_is_not_truncated() {
+        let (reader, truncated) = Limited::new(OneByte(&b"ab"[..]), 3);
+        assert_eq!(read_all(reader), b"ab");
+        assert!(!truncated.load(Ordering::Relaxed));
+    }
+}
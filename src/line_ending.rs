@@ -0,0 +1,193 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, Read, Write};
+
+/// A line ending to normalize text to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineEnding {
+    /// `\n`, used on Unix.
+    Lf,
+    /// `\r\n`, used on Windows.
+    CrLf,
+}
+
+/// Translates line endings across chunk boundaries.
+struct Translate {
+    ending: LineEnding,
+    /// The previous chunk ended with `\r`. Converting to LF holds back the `\r` until the next
+    /// byte shows whether it starts a CRLF.
+    cr: bool,
+}
+
+impl Translate {
+    fn new(ending: LineEnding) -> Self {
+        Self { ending, cr: false }
+    }
+
+    fn push(&mut self, input: &[u8], out: &mut Vec<u8>) {
+        for &b in input {
+            match self.ending {
+                LineEnding::Lf => {
+                    if self.cr && b != b'\n' {
+                        out.push(b'\r');
+                    }
+                    if b != b'\r' {
+                        out.push(b);
+                    }
+                }
+                LineEnding::CrLf => {
+                    if b == b'\n' && !self.cr {
+                        out.push(b'\r');
+                    }
+                    out.push(b);
+                }
+            }
+            self.cr = b == b'\r';
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<u8>) {
+        if self.ending == LineEnding::Lf && self.cr {
+            out.push(b'\r');
+        }
+        self.cr = false;
+    }
+}
+
+/// A reader that normalizes line endings.
+pub(crate) struct NormalizeReader<R> {
+    inner: R,
+    translate: Translate,
+    out: Vec<u8>,
+    pos: usize,
+    eof: bool,
+}
+
+impl<R> NormalizeReader<R> {
+    pub(crate) fn new(inner: R, ending: LineEnding) -> Self {
+        Self {
+            inner,
+            translate: Translate::new(ending),
+            out: Vec::new(),
+            pos: 0,
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> Read for NormalizeReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() && !self.eof && !buf.is_empty() {
+            let mut chunk = [0; 8192];
+            let n = self.inner.read(&mut chunk)?;
+            self.out.clear();
+            self.pos = 0;
+            if n == 0 {
+                self.translate.finish(&mut self.out);
+                self.eof = true;
+            } else {
+                self.translate.push(&chunk[..n], &mut self.out);
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// A writer that normalizes line endings.
+///
+/// A trailing `\r` converted to LF is held back until the next write or until the writer is
+/// dropped.
+pub(crate) struct NormalizeWriter<W: Write> {
+    inner: W,
+    translate: Translate,
+    out: Vec<u8>,
+}
+
+impl<W: Write> NormalizeWriter<W> {
+    pub(crate) fn new(inner: W, ending: LineEnding) -> Self {
+        Self {
+            inner,
+            translate: Translate::new(ending),
+            out: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> Write for NormalizeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.clear();
+        self.translate.push(buf, &mut self.out);
+        self.inner.write_all(&self.out)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<W: Write> Drop for NormalizeWriter<W> {
+    fn drop(&mut self) {
+        self.out.clear();
+        self.translate.finish(&mut self.out);
+        let _ = self.inner.write_all(&self.out);
+        let _ = self.inner.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{read_all, OneByte};
+
+    const CASES: &[(LineEnding, &[u8], &[u8])] = &[
+        (LineEnding::Lf, b"a\r\nb\r\n", b"a\nb\n"),
+        (LineEnding::Lf, b"a\rb\r\r\nc", b"a\rb\r\nc"),
+        (LineEnding::Lf, b"a\r", b"a\r"),
+        (LineEnding::CrLf, b"a\nb\r\nc\n", b"a\r\nb\r\nc\r\n"),
+        (LineEnding::CrLf, b"a\rb", b"a\rb"),
+    ];
+
+    #[test]
+    fn read_one_byte_at_a_time() {
+        for &(ending, input, expected) in CASES {
+            let reader = NormalizeReader::new(OneByte(input), ending);
+            assert_eq!(read_all(reader), expected, "{:?}", input);
+            let reader = NormalizeReader::new(input, ending);
+            assert_eq!(read_all(reader), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn write_one_byte_at_a_time() {
+        for &(ending, input, expected) in CASES {
+            let mut out = Vec::new();
+            let mut writer = NormalizeWriter::new(&mut out, ending);
+            for b in input {
+                writer.write_all(&[*b]).unwrap();
+            }
+            drop(writer);
+            assert_eq!(out, expected, "{:?}", input);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The bytes written to an output from [`Output::in_memory`](crate::Output::in_memory).
+#[derive(Debug, Clone, Default)]
+pub struct MemoryHandle {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl MemoryHandle {
+    /// Get a copy of the bytes written so far.
+    ///
+    /// Bytes still held in a buffer, like a [`BufferedOutput`](crate::BufferedOutput), only
+    /// show up once the writer is flushed or dropped.
+    pub fn contents(&self) -> Vec<u8> {
+        self.lock().clone()
+    }
+
+    pub(crate) fn writer(&self) -> MemoryWriter {
+        MemoryWriter {
+            bytes: Arc::clone(&self.bytes),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.bytes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// A writer appending to the bytes of a [`MemoryHandle`].
+pub(crate) struct MemoryWriter {
+    bytes: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut bytes = self.bytes.lock().unwrap_or_else(|e| e.into_inner());
+        bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
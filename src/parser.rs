@@ -0,0 +1,155 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::ffi::{OsStr, OsString};
+
+use clap::{
+    builder::{TypedValueParser, ValueParserFactory},
+    Arg, Command,
+};
+
+use crate::{Input, Output, STDIO};
+
+/// A clap value parser for [`Input`] with a configurable stdin sentinel.
+///
+/// By default `-` is parsed as stdin, like the [`FromStr`](std::str::FromStr) impl. Arguments
+/// are parsed from the raw [`OsStr`], so paths that aren't valid UTF-8 are preserved. This is
+/// the parser clap uses for an [`Input`] field unless another `value_parser` is given.
+///
+/// ```rust,no_run
+/// use clap::Parser;
+/// use clap_io::{Input, InputParser};
+///
+/// #[derive(Parser)]
+/// struct Cli {
+///     /// Treat `-` as a file name
+///     #[arg(long, value_parser = InputParser::new().no_stdio())]
+///     input: Input,
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct InputParser {
+    stdio: Option<OsString>,
+}
+
+impl InputParser {
+    /// Create a parser that treats `-` as stdin.
+    pub fn new() -> Self {
+        Self {
+            stdio: Some(STDIO.into()),
+        }
+    }
+
+    /// Treat `sentinel` as stdin instead of `-`.
+    pub fn stdio(mut self, sentinel: impl Into<OsString>) -> Self {
+        self.stdio = Some(sentinel.into());
+        self
+    }
+
+    /// Don't treat any argument other than `<stdin>` as stdin.
+    pub fn no_stdio(mut self) -> Self {
+        self.stdio = None;
+        self
+    }
+}
+
+impl Default for InputParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypedValueParser for InputParser {
+    type Value = Input;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        Ok(Input::parse(value, self.stdio.as_deref()))
+    }
+}
+
+impl ValueParserFactory for Input {
+    type Parser = InputParser;
+
+    fn value_parser() -> Self::Parser {
+        InputParser::new()
+    }
+}
+
+/// A clap value parser for [`Output`] with a configurable stdout sentinel.
+///
+/// By default `-` is parsed as stdout, like the [`FromStr`](std::str::FromStr) impl. Arguments
+/// are parsed from the raw [`OsStr`], so paths that aren't valid UTF-8 are preserved. This is
+/// the parser clap uses for an [`Output`] field unless another `value_parser` is given.
+#[derive(Debug, Clone)]
+pub struct OutputParser {
+    stdio: Option<OsString>,
+}
+
+impl OutputParser {
+    /// Create a parser that treats `-` as stdout.
+    pub fn new() -> Self {
+        Self {
+            stdio: Some(STDIO.into()),
+        }
+    }
+
+    /// Treat `sentinel` as stdout instead of `-`.
+    pub fn stdio(mut self, sentinel: impl Into<OsString>) -> Self {
+        self.stdio = Some(sentinel.into());
+        self
+    }
+
+    /// Don't treat any argument other than `<stdout>` as stdout.
+    pub fn no_stdio(mut self) -> Self {
+        self.stdio = None;
+        self
+    }
+}
+
+impl Default for OutputParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TypedValueParser for OutputParser {
+    type Value = Output;
+
+    fn parse_ref(
+        &self,
+        _cmd: &Command,
+        _arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        Ok(Output::parse(value, self.stdio.as_deref()))
+    }
+}
+
+impl ValueParserFactory for Output {
+    type Parser = OutputParser;
+
+    fn value_parser() -> Self::Parser {
+        OutputParser::new()
+    }
+}
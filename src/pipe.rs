@@ -0,0 +1,116 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, Write};
+
+/// An output stream that stops writing once the reader closes the pipe, returned by
+/// [`Output::open_ignore_broken_pipe`](crate::Output::open_ignore_broken_pipe).
+///
+/// After a write or flush fails with [`io::ErrorKind::BrokenPipe`], the error is swallowed
+/// and everything written afterwards is discarded. Check [`PipeOutput::is_closed`] to stop
+/// processing early.
+pub struct PipeOutput {
+    inner: Box<dyn Write + 'static>,
+    closed: bool,
+}
+
+impl PipeOutput {
+    pub(crate) fn new(inner: Box<dyn Write + 'static>) -> Self {
+        Self {
+            inner,
+            closed: false,
+        }
+    }
+
+    /// Has the reader closed the pipe?
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn check<T>(&mut self, result: io::Result<T>, closed: T) -> io::Result<T> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(closed)
+            }
+            result => result,
+        }
+    }
+}
+
+impl Write for PipeOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.closed {
+            return Ok(buf.len());
+        }
+        let result = self.inner.write(buf);
+        self.check(result, buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let result = self.inner.flush();
+        self.check(result, ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_util::Failing, MemoryHandle};
+
+    #[test]
+    fn writes_pass_through_while_open() {
+        let handle = MemoryHandle::default();
+        let mut output = PipeOutput::new(Box::new(handle.writer()));
+        output.write_all(b"data").unwrap();
+        output.flush().unwrap();
+        assert!(!output.is_closed());
+        assert_eq!(handle.contents(), b"data");
+    }
+
+    #[test]
+    fn broken_pipe_closes_the_output() {
+        let mut output = PipeOutput::new(Box::new(Failing(io::ErrorKind::BrokenPipe)));
+        assert_eq!(output.write(b"data").unwrap(), 4);
+        assert!(output.is_closed());
+        // Later writes and flushes are discarded without reaching the inner writer
+        output.write_all(b"more").unwrap();
+        output.flush().unwrap();
+    }
+
+    #[test]
+    fn broken_pipe_on_flush_closes_the_output() {
+        let mut output = PipeOutput::new(Box::new(Failing(io::ErrorKind::BrokenPipe)));
+        output.flush().unwrap();
+        assert!(output.is_closed());
+    }
+
+    #[test]
+    fn other_errors_are_returned() {
+        let mut output = PipeOutput::new(Box::new(Failing(io::ErrorKind::PermissionDenied)));
+        let e = output.write(b"data").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+        let e = output.flush().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::PermissionDenied);
+        assert!(!output.is_closed());
+    }
+}
@@ -0,0 +1,155 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{io, thread, time::Duration};
+
+use crate::OpenError;
+
+/// When to retry opening a stream that failed with a transient error.
+///
+/// Used by [`Input::open_with_retry`](crate::Input::open_with_retry) and
+/// [`Output::open_with_retry`](crate::Output::open_with_retry). The delay doubles after each
+/// failed attempt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+    kinds: Vec<io::ErrorKind>,
+}
+
+impl RetryPolicy {
+    /// Make 3 attempts, waiting 100ms and then 200ms in between, retrying `Interrupted`,
+    /// `WouldBlock` and `TimedOut` errors.
+    pub fn new() -> Self {
+        Self {
+            attempts: 3,
+            base_delay: Duration::from_millis(100),
+            kinds: vec![
+                io::ErrorKind::Interrupted,
+                io::ErrorKind::WouldBlock,
+                io::ErrorKind::TimedOut,
+            ],
+        }
+    }
+
+    /// Make at most `attempts` attempts, including the first.
+    pub fn attempts(mut self, attempts: u32) -> Self {
+        self.attempts = attempts;
+        self
+    }
+
+    /// Wait `delay` after the first failed attempt.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Retry errors of these kinds instead of the defaults.
+    pub fn kinds(mut self, kinds: impl IntoIterator<Item = io::ErrorKind>) -> Self {
+        self.kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Call `open` until it succeeds, fails with an error that isn't retried, or runs out of
+    /// attempts, returning the last error.
+    pub(crate) fn retry<T>(
+        &self,
+        mut open: impl FnMut() -> Result<T, OpenError>,
+    ) -> Result<T, OpenError> {
+        let mut delay = self.base_delay;
+        let mut attempt = 1;
+        loop {
+            match open() {
+                Err(e) if attempt < self.attempts && self.kinds.contains(&e.kind()) => {
+                    thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Input;
+
+    /// Fail with each error in turn, then succeed, counting the attempts
+    fn attempts(policy: &RetryPolicy, errors: &[io::ErrorKind]) -> (Result<(), OpenError>, usize) {
+        let stream = Input::from_path("retried").0;
+        let mut count = 0;
+        let result = policy.retry(|| {
+            count += 1;
+            match errors.get(count - 1) {
+                Some(&kind) => Err(OpenError::input(&stream, kind.into())),
+                None => Ok(()),
+            }
+        });
+        (result, count)
+    }
+
+    #[test]
+    fn retries_until_success() {
+        let policy = RetryPolicy::new().base_delay(Duration::ZERO);
+        let errors = [io::ErrorKind::Interrupted, io::ErrorKind::TimedOut];
+        let (result, count) = attempts(&policy, &errors);
+        assert!(result.is_ok());
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn returns_the_last_error_when_out_of_attempts() {
+        let policy = RetryPolicy::new().attempts(2).base_delay(Duration::ZERO);
+        let errors = [
+            io::ErrorKind::Interrupted,
+            io::ErrorKind::TimedOut,
+            io::ErrorKind::Interrupted,
+        ];
+        let (result, count) = attempts(&policy, &errors);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::TimedOut);
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn other_kinds_are_not_retried() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::ZERO)
+            .kinds([io::ErrorKind::TimedOut]);
+        let errors = [io::ErrorKind::Interrupted];
+        let (result, count) = attempts(&policy, &errors);
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Interrupted);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn one_attempt_never_retries() {
+        let policy = RetryPolicy::new().attempts(1).base_delay(Duration::ZERO);
+        let (result, count) = attempts(&policy, &[io::ErrorKind::Interrupted]);
+        assert!(result.is_err());
+        assert_eq!(count, 1);
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{borrow::Cow, ffi::OsStr};
+
+use serde::{ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{escape_path, Input, Output, Stream, FD_PREFIX, STDERR, STDIO};
+
+impl Stream {
+    /// The string a stream serializes to, which parses back into the same stream.
+    ///
+    /// A file path that `is_file` says wouldn't parse back as a file is escaped.
+    fn to_serde_str(&self, is_file: impl Fn(&OsStr) -> bool) -> Result<Cow<'_, str>, &'static str> {
+        match self {
+            Self::File(path) => escape_path(path.clone(), is_file)
+                .into_string()
+                .map(Cow::Owned)
+                .map_err(|_| "path contains invalid UTF-8"),
+            Self::Stdin { .. } | Self::Stdout { .. } => Ok(STDIO.into()),
+            Self::Stderr { .. } => Ok(STDERR.into()),
+            Self::Fd { fd, .. } => Ok(format!("{}{}", FD_PREFIX, fd).into()),
+            #[cfg(feature = "http")]
+            Self::Url(url) => Ok(url.as_str().into()),
+            #[cfg(feature = "clipboard")]
+            Self::Clipboard => Ok(crate::CLIPBOARD.into()),
+            Self::Null => Ok(crate::NULL.into()),
+            Self::Reader(_) | Self::Writer(_) => Err("in-memory streams can't be serialized"),
+        }
+    }
+}
+
+impl Serialize for Input {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let s = self
+            .0
+            .to_serde_str(|s| Input::from(s).is_file())
+            .map_err(ser::Error::custom)?;
+        serializer.serialize_str(&s)
+    }
+}
+
+impl Serialize for Output {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let s = self
+            .0
+            .to_serde_str(|s| Output::from(s).is_file())
+            .map_err(ser::Error::custom)?;
+        serializer.serialize_str(&s)
+    }
+}
+
+impl<'de> Deserialize<'de> for Input {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = Cow::<str>::deserialize(deserializer)?;
+        Ok(Self::from(OsStr::new(s.as_ref())))
+    }
+}
+
+impl<'de> Deserialize<'de> for Output {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = Cow::<str>::deserialize(deserializer)?;
+        Ok(Self::from(OsStr::new(s.as_ref())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sentinel_file_round_trip() {
+        for path in ["-", "<stdin>", "<stdout>", "<null>", "in.txt"] {
+            let input = Input::from_path(path);
+            let json = serde_json::to_string(&input).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Input>(&json).unwrap(),
+                input,
+                "{}",
+                json
+            );
+            let output = Output::from_path(path);
+            let json = serde_json::to_string(&output).unwrap();
+            assert_eq!(
+                serde_json::from_str::<Output>(&json).unwrap(),
+                output,
+                "{}",
+                json
+            );
+        }
+        let json = serde_json::to_string(&Input::stdin()).unwrap();
+        assert_eq!(json, r#""-""#);
+        let json = serde_json::to_string(&Output::sink()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Output>(&json).unwrap(),
+            Output::sink()
+        );
+    }
+}
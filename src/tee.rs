@@ -0,0 +1,114 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, Write};
+
+/// A writer that copies every write to each of its writers.
+///
+/// Writes stop at the first error. Every writer is flushed when the tee is dropped.
+pub(crate) struct Tee {
+    writers: Vec<Box<dyn Write + 'static>>,
+}
+
+impl Tee {
+    pub(crate) fn new(writers: Vec<Box<dyn Write + 'static>>) -> Self {
+        Self { writers }
+    }
+}
+
+impl Write for Tee {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for writer in &mut self.writers {
+            writer.write_all(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for writer in &mut self.writers {
+            if let Err(e) = writer.flush() {
+                result = result.and(Err(e));
+            }
+        }
+        result
+    }
+}
+
+impl Drop for Tee {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::{test_util::Failing, MemoryHandle};
+
+    /// A writer that records whether it was flushed.
+    struct FlushFlag(Rc<Cell<bool>>);
+
+    impl Write for FlushFlag {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.set(true);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_copies_to_every_writer() {
+        let (a, b) = (MemoryHandle::default(), MemoryHandle::default());
+        let mut tee = Tee::new(vec![Box::new(a.writer()), Box::new(b.writer())]);
+        tee.write_all(b"data").unwrap();
+        drop(tee);
+        assert_eq!(a.contents(), b"data");
+        assert_eq!(b.contents(), b"data");
+    }
+
+    #[test]
+    fn write_stops_at_first_error() {
+        let a = MemoryHandle::default();
+        let mut tee = Tee::new(vec![
+            Box::new(Failing(io::ErrorKind::BrokenPipe)),
+            Box::new(a.writer()),
+        ]);
+        let e = tee.write(b"data").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::BrokenPipe);
+        assert!(a.contents().is_empty());
+    }
+
+    #[test]
+    fn flush_reports_first_error_after_flushing_all() {
+        let flushed = Rc::new(Cell::new(false));
+        let mut tee = Tee::new(vec![
+            Box::new(Failing(io::ErrorKind::Other)),
+            Box::new(Failing(io::ErrorKind::BrokenPipe)),
+            Box::new(FlushFlag(Rc::clone(&flushed))),
+        ]);
+        assert_eq!(tee.flush().unwrap_err().kind(), io::ErrorKind::Other);
+        assert!(flushed.get());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::{
+    io::{self, Read, Write},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// A reader or writer limited to a number of bytes per second by a token bucket.
+///
+/// The bucket holds up to one second of bytes, so short bursts can exceed the rate.
+pub(crate) struct Throttled<T> {
+    inner: T,
+    rate: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+/// Check that a throttle rate is valid, before opening the stream it applies to.
+pub(crate) fn rate(bytes_per_sec: u64) -> io::Result<f64> {
+    if bytes_per_sec == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Throttle rate must be at least one byte per second",
+        ));
+    }
+    Ok(bytes_per_sec as f64)
+}
+
+impl<T> Throttled<T> {
+    pub(crate) fn new(inner: T, rate: f64) -> Self {
+        Self {
+            inner,
+            rate,
+            tokens: rate,
+            last: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last = now;
+    }
+
+    /// Wait until enough bytes are available, then get how many of `len` may be used.
+    ///
+    /// Waits for up to 10ms worth of bytes at a time so slow rates don't degrade into
+    /// single byte reads or writes.
+    fn acquire(&mut self, len: usize) -> usize {
+        self.refill();
+        let wanted = (len as f64).min(self.rate / 100.0).max(1.0);
+        if self.tokens < wanted {
+            thread::sleep(Duration::from_secs_f64((wanted - self.tokens) / self.rate));
+            self.refill();
+        }
+        len.min(self.tokens as usize).max(1)
+    }
+
+    fn consume(&mut self, n: usize) {
+        self.tokens -= n as f64;
+    }
+}
+
+impl<T: Read> Read for Throttled<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = self.acquire(buf.len());
+        let n = self.inner.read(&mut buf[..len])?;
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Throttled<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let len = self.acquire(buf.len());
+        let n = self.inner.write(&buf[..len])?;
+        self.consume(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_rate_is_invalid() {
+        assert_eq!(rate(0).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn full_bucket_allows_one_second_at_once() {
+        let mut throttled = Throttled::new(io::sink(), 1000.0);
+        let start = Instant::now();
+        assert_eq!(throttled.acquire(10_000), 1000);
+        assert!(start.elapsed() < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn empty_bucket_waits_for_a_slice() {
+        let mut throttled = Throttled::new(io::sink(), 100_000.0);
+        throttled.consume(100_000);
+        let start = Instant::now();
+        let n = throttled.acquire(100_000);
+        // Only 10ms worth of bytes is waited for, not the second the whole request needs
+        let elapsed = start.elapsed();
+        assert!(elapsed >= Duration::from_millis(10), "{:?}", elapsed);
+        assert!(elapsed < Duration::from_millis(500), "{:?}", elapsed);
+        assert!(n >= 1000, "{}", n);
+    }
+
+    #[test]
+    fn writes_are_split_by_the_rate() {
+        let mut throttled = Throttled::new(Vec::new(), 1000.0);
+        assert_eq!(throttled.write(&[0; 4000]).unwrap(), 1000);
+        assert_eq!(throttled.inner.len(), 1000);
+    }
+}
@@ -0,0 +1,86 @@
+// Copyright (c) 2023 Swift Navigation
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy of
+// this software and associated documentation files (the "Software"), to deal in
+// the Software without restriction, including without limitation the rights to
+// use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of
+// the Software, and to permit persons to whom the Software is furnished to do so,
+// subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS
+// FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR
+// COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER
+// IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN
+// CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+
+use std::io::{self, BufRead, Read, Seek, Write};
+
+/// Emits a debug event when a stream is opened, and another when this is dropped.
+pub(crate) struct Opened {
+    kind: &'static str,
+    name: String,
+}
+
+impl Opened {
+    pub(crate) fn new(kind: &'static str, name: String) -> Self {
+        tracing::debug!(kind, name = %name, "opened stream");
+        Self { kind, name }
+    }
+}
+
+impl Drop for Opened {
+    fn drop(&mut self) {
+        tracing::debug!(kind = self.kind, name = %self.name, "closed stream");
+    }
+}
+
+/// A reader or writer that emits the events of [`Opened`] for its stream.
+pub(crate) struct Traced<T> {
+    inner: T,
+    _opened: Opened,
+}
+
+impl<T> Traced<T> {
+    pub(crate) fn new(inner: T, opened: Opened) -> Self {
+        Self {
+            inner,
+            _opened: opened,
+        }
+    }
+}
+
+impl<T: Read> Read for Traced<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<T: BufRead> BufRead for Traced<T> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl<T: Seek> Seek for Traced<T> {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+impl<T: Write> Write for Traced<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}